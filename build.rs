@@ -0,0 +1,80 @@
+//! With the `embed` feature, validates `dictionaries/*.toml` and embeds them into the binary,
+//! so that the bot runs without `DIC_DIR`.
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// The format `Dictionary` reads, so that every field is checked before anything is embedded.
+#[allow(dead_code)]
+#[path = "src/schema.rs"]
+mod schema;
+use schema::{Entry, RawDictionary};
+
+fn is_valid_text(text: &str) -> bool {
+    !text.trim().is_empty() && !text.contains(|c: char| c.is_control() && c != '\n')
+}
+
+fn validate_entry(entry: &Entry) -> Result<(), String> {
+    let texts = [&entry.meaning, &entry.reading, &entry.pos, &entry.source];
+    let texts = texts
+        .iter()
+        .filter_map(|text| text.as_deref())
+        .chain(entry.tags.iter().map(String::as_str));
+    for text in texts {
+        if !is_valid_text(text) {
+            return Err(format!("invalid text {:?} of {:?}", text, entry.word));
+        }
+    }
+    Ok(())
+}
+
+fn validate(path: &Path) -> Result<(), String> {
+    let buffer = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let raw: RawDictionary = toml::from_str(&buffer).map_err(|err| err.to_string())?;
+    if raw.questions.is_empty() && raw.entries.is_empty() {
+        return Err("no questions".to_string());
+    }
+    let words = raw
+        .questions
+        .iter()
+        .chain(raw.full.iter().flatten())
+        .chain(raw.entries.iter().map(|entry| &entry.word));
+    for word in words {
+        if word.trim().is_empty() || word.contains(|c: char| c.is_control()) {
+            return Err(format!("invalid word {:?}", word));
+        }
+    }
+    raw.entries.iter().try_for_each(validate_entry)
+}
+
+fn main() {
+    if env::var("CARGO_FEATURE_EMBED").is_err() {
+        return;
+    }
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("embedded.rs");
+    let dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("dictionaries");
+    println!("cargo:rerun-if-changed={}", dir.display());
+    let mut paths = fs::read_dir(&dir)
+        .unwrap_or_else(|err| panic!("{}: {}", dir.display(), err))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "toml")
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    let mut code = String::from("pub(crate) const EMBEDDED: &[(&str, &str)] = &[\n");
+    for path in paths {
+        println!("cargo:rerun-if-changed={}", path.display());
+        if let Err(why) = validate(&path) {
+            panic!("malformed dictionary {}: {}", path.display(), why);
+        }
+        code += &format!(
+            "    ({:?}, include_str!({:?})),\n",
+            path.file_stem().unwrap().to_string_lossy(),
+            path.display().to_string()
+        );
+    }
+    code += "];\n";
+    fs::write(&out, code).unwrap();
+}
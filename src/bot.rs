@@ -1,15 +1,30 @@
+use super::commands::parser::SurvivalOption;
 use super::dictionary::*;
+use super::double::Double;
+use super::history;
+use super::hunt::Hunt;
+use super::letters::Letters;
+use super::puzzle::{self, Puzzle};
+use super::rating;
 use super::sort::Sorted;
+use super::coop::Coop;
+use super::shiritori::Chain;
+use super::survival::Survival;
+use super::team::Teams;
 use indexmap::{IndexMap, IndexSet};
 use itertools::Itertools;
-use rand::distributions::{Distribution, Uniform};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 use serenity::client::Context;
 use serenity::model::channel::Message;
+use serenity::model::user::User;
 use crate::try_say;
 
+use std::collections::VecDeque;
 use std::ops::AddAssign;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
+use unicode_segmentation::UnicodeSegmentation;
 
 custom_derive! {
     #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, NextVariant, PrevVariant)]
@@ -36,6 +51,31 @@ impl Lang {
             Lang::Eo => "エスペラント単語".to_string(),
         }
     }
+
+    pub fn as_code(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::Ja => "ja",
+            Lang::Fr => "fr",
+            Lang::De => "de",
+            Lang::It => "it",
+            Lang::Ru => "ru",
+            Lang::Eo => "eo",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Lang> {
+        match code {
+            "en" => Some(Lang::En),
+            "ja" => Some(Lang::Ja),
+            "fr" => Some(Lang::Fr),
+            "de" => Some(Lang::De),
+            "it" => Some(Lang::It),
+            "ru" => Some(Lang::Ru),
+            "eo" => Some(Lang::Eo),
+            _ => None,
+        }
+    }
 }
 
 impl<S: Into<String>> From<S> for Lang {
@@ -83,8 +123,22 @@ pub fn select_dictionary_from_str<S: Into<String>>(lang: S) -> &'static Dictiona
 #[derive(Debug)]
 pub enum Status {
     StandingBy,
-    Holding(String, Lang, Instant),
-    Contesting(String, Lang, (u32, u32), Instant),
+    Holding(String, Lang, Instant, Box<dyn Puzzle>),
+    Contesting(String, Lang, (u32, u32), Instant, Box<dyn Puzzle>),
+    /// Waiting for `~join` before a contest of the given number of problems.
+    Recruiting(u32, Instant),
+    /// A round of the survival mode, with its players and deadline.
+    Surviving(String, Lang, Survival),
+    /// The co-op mode, which has several puzzles open at once.
+    Cooperating(Coop),
+    /// A shiritori chain, whose next puzzle starts with the last letter of the previous answer.
+    Chaining(String, Lang, Chain, Instant),
+    /// A letters round, where the longest word made of the drawn letters wins.
+    Lettering(Letters),
+    /// An anagram hunt, where players find as many words as possible from the letters of a word.
+    Hunting(Hunt),
+    /// A puzzle of two words merged, whose answer is the pair of them.
+    Doubling(Double),
 }
 
 pub enum CheckResult<'a> {
@@ -113,50 +167,181 @@ impl Status {
             _ => false,
         }
     }
+    pub fn is_recruiting(&self) -> bool {
+        match self {
+            Status::Recruiting(..) => true,
+            _ => false,
+        }
+    }
+    pub fn is_surviving(&self) -> bool {
+        match self {
+            Status::Surviving(..) => true,
+            _ => false,
+        }
+    }
+    pub fn is_cooperating(&self) -> bool {
+        match self {
+            Status::Cooperating(..) => true,
+            _ => false,
+        }
+    }
+    pub fn is_chaining(&self) -> bool {
+        match self {
+            Status::Chaining(..) => true,
+            _ => false,
+        }
+    }
+    pub fn is_lettering(&self) -> bool {
+        match self {
+            Status::Lettering(..) => true,
+            _ => false,
+        }
+    }
+    pub fn is_hunting(&self) -> bool {
+        match self {
+            Status::Hunting(..) => true,
+            _ => false,
+        }
+    }
+    pub fn is_doubling(&self) -> bool {
+        match self {
+            Status::Doubling(..) => true,
+            _ => false,
+        }
+    }
+    /// Whether a game other than a single quiz is running.
+    pub fn is_game(&self) -> bool {
+        !self.is_standing_by() && !self.is_holding()
+    }
 
     pub fn ans(&self) -> std::result::Result<&String, ()> {
         match self {
-            Status::StandingBy => Err(()),
-            Status::Holding(ans, ..) | Status::Contesting(ans, ..) => Ok(ans),
+            Status::StandingBy
+            | Status::Recruiting(..)
+            | Status::Cooperating(..)
+            | Status::Lettering(..)
+            | Status::Hunting(..)
+            | Status::Doubling(..) => Err(()),
+            Status::Holding(ans, ..)
+            | Status::Contesting(ans, ..)
+            | Status::Surviving(ans, ..)
+            | Status::Chaining(ans, ..) => Ok(ans),
         }
     }
 
     pub fn get_dictionary(&self) -> Result<&Dictionary, ()> {
         match self {
-            Status::StandingBy => Err(()),
-            Status::Contesting(_, lang, ..) | Status::Holding(_, lang, ..) => {
-                Ok(get_dictionary(*lang))
-            }
+            Status::StandingBy
+            | Status::Recruiting(..)
+            | Status::Cooperating(..)
+            | Status::Lettering(..)
+            | Status::Hunting(..)
+            | Status::Doubling(..) => Err(()),
+            Status::Contesting(_, lang, ..)
+            | Status::Holding(_, lang, ..)
+            | Status::Surviving(_, lang, ..)
+            | Status::Chaining(_, lang, ..) => Ok(get_dictionary(*lang)),
+        }
+    }
+
+    /// Normalizes a guess as the words of the dictionary of the quiz are.
+    fn normalize(&self, got: &str) -> String {
+        match self.get_dictionary() {
+            Ok(dic) => dic.normalize(got),
+            Err(_) => got.to_lowercase(),
         }
     }
 
     pub fn is_correct_answer(&self, got: &str) -> bool {
         match self {
-            Status::StandingBy => false,
-            Status::Contesting(ans, ..) | Status::Holding(ans, ..) => ans == &got.to_lowercase(),
+            Status::StandingBy
+            | Status::Recruiting(..)
+            | Status::Cooperating(..)
+            | Status::Lettering(..)
+            | Status::Hunting(..)
+            | Status::Doubling(..) => false,
+            Status::Contesting(ans, ..)
+            | Status::Holding(ans, ..)
+            | Status::Surviving(ans, ..)
+            | Status::Chaining(ans, ..) => ans == &self.normalize(got),
         }
     }
 
     pub fn is_anagram(&self, got: &str) -> bool {
         match self {
-            Status::StandingBy => false,
-            Status::Contesting(ans, ..) | Status::Holding(ans, ..) => {
-                ans.sorted() == got.to_lowercase().sorted()
-                    && self.get_dictionary().unwrap().contains(&got.to_lowercase())
+            Status::StandingBy
+            | Status::Recruiting(..)
+            | Status::Cooperating(..)
+            | Status::Lettering(..)
+            | Status::Hunting(..)
+            | Status::Doubling(..) => false,
+            Status::Contesting(..)
+            | Status::Holding(..)
+            | Status::Surviving(..)
+            | Status::Chaining(..) => {
+                let got = self.normalize(got);
+                self.accepts(&got) && self.get_dictionary().unwrap().contains(&got)
             }
         }
     }
 
     pub fn is_anagram_by_full(&self, got: &str) -> bool {
         match self {
-            Status::StandingBy => false,
+            Status::StandingBy
+            | Status::Recruiting(..)
+            | Status::Cooperating(..)
+            | Status::Lettering(..)
+            | Status::Hunting(..)
+            | Status::Doubling(..) => false,
             _ => {
-                self.ans().unwrap().sorted() == got.to_lowercase().sorted()
-                    && self
-                        .get_dictionary()
-                        .unwrap()
-                        .contains_ex(&got.to_lowercase())
+                let got = self.normalize(got);
+                self.accepts(&got) && self.get_dictionary().unwrap().contains_ex(&got)
+            }
+        }
+    }
+
+    /// The puzzle of a single quiz or a contest problem. Other modes are always sorted.
+    pub fn puzzle(&self) -> Option<&dyn Puzzle> {
+        match self {
+            Status::Holding(_, _, _, puzzle) | Status::Contesting(_, _, _, _, puzzle) => {
+                Some(puzzle.as_ref())
             }
+            _ => None,
+        }
+    }
+
+    /// The problem shown to players.
+    pub fn problem(&self) -> Result<String, ()> {
+        let ans = self.ans()?;
+        Ok(match self.puzzle() {
+            Some(puzzle) => puzzle.display(ans),
+            None => ans.sorted(),
+        })
+    }
+
+    /// Graphemes which hints are taken from.
+    pub fn hints(&self) -> Result<Vec<&str>, ()> {
+        let ans = self.ans()?;
+        Ok(match self.puzzle() {
+            Some(puzzle) => puzzle.hints(ans),
+            None => UnicodeSegmentation::graphemes(ans.as_str(), true).collect(),
+        })
+    }
+
+    /// The dictionary entry of the answer, as lines following it.
+    pub fn describe(&self) -> String {
+        match (self.ans(), self.get_dictionary()) {
+            (Ok(ans), Ok(dic)) => dic.describe(ans),
+            _ => String::new(),
+        }
+    }
+
+    /// Whether `got` is an arrangement the problem asks for.
+    fn accepts(&self, got: &str) -> bool {
+        match (self.ans(), self.puzzle()) {
+            (Ok(ans), Some(puzzle)) => puzzle.accepts(ans, got),
+            (Ok(ans), None) => ans.sorted() == got.to_string().sorted(),
+            (Err(_), _) => false,
         }
     }
 
@@ -184,42 +369,65 @@ impl Status {
     }
 
     pub fn contest_continue(&mut self, ctx: &mut Context, msg: &Message) {
-        let (dic, lang) = CONTEST_LIBRARY
-            .lock()
-            .unwrap()
-            .select(&mut rand::thread_rng());
-        let ans = dic.get(&mut rand::thread_rng());
-        let sorted = ans.sorted();
-        println!("called contest_continue: [{}, {}]", ans, sorted);
-        let (count, num) = self.get_contest_num().unwrap();
+        let library = &mut *CONTEST_LIBRARY.lock().unwrap();
+        let (ans, lang) = library.next();
+        let (count, num) = self.get_contest_num().map(|(c, n)| (*c, *n)).unwrap();
+        let kind = self.puzzle().unwrap().kind();
+        let puzzle = kind.make(
+            &ans,
+            get_dictionary(lang),
+            &mut library.puzzle_rng(count + 1),
+        );
+        let prob = puzzle.display(&ans);
+        println!("called contest_continue: [{}, {}]", ans, prob);
+        if let Err(why) = history::record(*msg.channel_id.as_u64(), lang, &ans) {
+            println!("{}", why);
+        }
         try_say!(
             ctx,
             msg,
             format!(
-                "問 {current} ({current}/{number})\nソートなぞなぞ ソート前の {symbol} な〜んだ？\n`{prob}`",
+                "問 {current} ({current}/{number})\nソートなぞなぞ {statement} {symbol} な〜んだ？\n`{prob}`",
                 number = num,
-                current = *count + 1,
-                prob = sorted,
+                current = count + 1,
+                statement = kind.statement(),
+                prob = prob,
                 symbol = lang.as_symbol(),
             )
         );
-        *self = Status::Contesting(ans.to_string(), lang, (*count + 1, *num), Instant::now());
+        *self = Status::Contesting(ans, lang, (count + 1, num), Instant::now(), puzzle);
     }
 
     pub fn elapsed(&self) -> Option<f32> {
         match self {
-            Status::Holding(_, _, instant) => Some(instant.elapsed().as_secs_f32()),
-            Status::Contesting(_, _, _, instant) => Some(instant.elapsed().as_secs_f32()),
+            Status::Holding(_, _, instant, _) => Some(instant.elapsed().as_secs_f32()),
+            Status::Contesting(_, _, _, instant, _) => Some(instant.elapsed().as_secs_f32()),
+            Status::Chaining(_, _, _, instant) => Some(instant.elapsed().as_secs_f32()),
+            Status::Surviving(_, _, survival) => Some(survival.started.elapsed().as_secs_f32()),
             _ => None,
         }
     }
 }
 
+/// Draws contest problems: picks a language by weight (or by a fixed quota)
+/// and a word that has not been asked yet in the contest.
+/// Problems are drawn from a seeded engine so that a contest can be replayed.
+#[derive(Debug)]
 pub struct DictionarySelector {
-    engine: Result<Lang, Uniform<usize>>,
-    set: IndexSet<Lang>,
+    engine: Result<Pool, WeightedIndex<u32>>,
+    set: IndexMap<Pool, u32>,
+    quota: bool,
+    used: IndexSet<(Lang, String)>,
+    avoid: IndexSet<(Lang, String)>,
+    seed: u64,
+    rng: StdRng,
+    preset: VecDeque<(Lang, String)>,
+    problems: Vec<(Lang, String)>,
 }
 
+/// Words of a language, only those with the tag if any.
+type Pool = (Lang, Option<String>);
+
 impl Default for DictionarySelector {
     fn default() -> Self {
         Self::new()
@@ -229,36 +437,164 @@ impl Default for DictionarySelector {
 impl DictionarySelector {
     pub fn new() -> DictionarySelector {
         DictionarySelector {
-            engine: Ok(Lang::En),
+            engine: Ok((Lang::En, None)),
             set: Default::default(),
+            quota: false,
+            used: Default::default(),
+            avoid: Default::default(),
+            seed: 0,
+            rng: StdRng::seed_from_u64(0),
+            preset: Default::default(),
+            problems: Default::default(),
         }
     }
-    pub fn set<S: Into<String>>(&mut self, languages: Vec<S>) {
-        if languages.len() == 1 {
-            self.engine = Ok(Lang::from(languages.into_iter().next().unwrap()));
-        } else {
-            self.engine = Err(Uniform::new(0, languages.len()));
-            for lang in languages {
-                self.set.insert(Lang::from(lang));
-            }
+
+    fn reset(&mut self, seed: u64) {
+        self.set.clear();
+        self.used.clear();
+        self.avoid.clear();
+        self.preset.clear();
+        self.problems.clear();
+        self.quota = false;
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Resets the selector for a new contest.
+    /// With `quota`, each weight is the exact number of problems of that language.
+    /// With `tag`, only the words with it are asked.
+    pub fn set<S: Into<String>>(
+        &mut self,
+        languages: Vec<(S, u32)>,
+        quota: bool,
+        seed: u64,
+        tag: Option<&str>,
+    ) {
+        self.reset(seed);
+        self.quota = quota;
+        for (lang, weight) in languages {
+            let pool = (Lang::from(lang), tag.map(str::to_string));
+            *self.set.entry(pool).or_insert(0) += weight;
+        }
+        self.reset_engine();
+    }
+
+    /// Resets the selector to ask exactly `problems`, in order unless `shuffle`.
+    pub fn preset(&mut self, mut problems: Vec<(Lang, String)>, seed: u64, shuffle: bool) {
+        self.reset(seed);
+        if shuffle {
+            problems.shuffle(&mut self.rng);
         }
+        self.preset = problems.into_iter().collect();
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Problems asked since the last reset, in order.
+    pub fn problems(&self) -> &[(Lang, String)] {
+        &self.problems
+    }
+
+    /// Engine for random hints of the `count`-th problem, independent of problem selection.
+    pub fn hint_rng(&self, count: u32) -> StdRng {
+        StdRng::seed_from_u64(self.seed.wrapping_add(u64::from(count)))
     }
-    pub fn select<Engine: rand::Rng>(&self, rng: &mut Engine) -> (&'static Dictionary, Lang) {
-        let lang = *self
+
+    /// Engine for the puzzle of the `count`-th problem, such as the order of a shuffle.
+    pub fn puzzle_rng(&self, count: u32) -> StdRng {
+        StdRng::seed_from_u64(!self.seed.wrapping_add(u64::from(count)))
+    }
+
+    /// Words which should not be asked unless nothing else is left.
+    pub fn avoid(&mut self, words: IndexSet<(Lang, String)>) {
+        self.avoid = words;
+    }
+
+    fn reset_engine(&mut self) {
+        self.engine = if self.set.len() == 1 {
+            Ok(self.set.get_index(0).unwrap().0.clone())
+        } else {
+            WeightedIndex::new(self.set.values()).map_or(Ok((Lang::En, None)), Err)
+        };
+    }
+
+    pub fn select<Engine: rand::Rng>(&mut self, rng: &mut Engine) -> (&'static Dictionary, Pool) {
+        let pool = self
             .engine
             .as_ref()
-            .unwrap_or_else(|uniform| self.set.get_index(uniform.sample(rng)).unwrap());
-        (get_dictionary(lang), lang)
+            .unwrap_or_else(|weighted| self.set.get_index(weighted.sample(rng)).unwrap().0)
+            .clone();
+        if self.quota {
+            if let Some(remaining) = self.set.get_mut(&pool) {
+                *remaining = remaining.saturating_sub(1);
+            }
+            if self.set.values().any(|remaining| *remaining > 0) {
+                self.set.retain(|_, remaining| *remaining > 0);
+                self.reset_engine();
+            }
+        }
+        (get_dictionary(pool.0), pool)
+    }
+
+    /// Picks the next contest problem without repeating a word of the current contest.
+    pub fn next_problem<Engine: rand::Rng>(&mut self, rng: &mut Engine) -> (String, Lang) {
+        let (dic, (lang, tag)) = self.select(rng);
+        let tag = tag.as_deref();
+        let (used, avoid) = (&self.used, &self.avoid);
+        let ans = dic
+            .get_tagged_except(rng, tag, |word| {
+                let key = (lang, word.to_string());
+                used.contains(&key) || avoid.contains(&key)
+            })
+            .or_else(|| {
+                dic.get_tagged_except(rng, tag, |word| used.contains(&(lang, word.to_string())))
+            })
+            .or_else(|| dic.get_tagged(rng, tag))
+            .unwrap_or_else(|| dic.get(rng))
+            .to_string();
+        self.used.insert((lang, ans.clone()));
+        (ans, lang)
+    }
+
+    /// Picks the next problem with the selector's own engine, or the next preset one.
+    pub fn next(&mut self) -> (String, Lang) {
+        let (lang, ans) = match self.preset.pop_front() {
+            Some(problem) => problem,
+            None => {
+                let mut rng = self.rng.clone();
+                let (ans, lang) = self.next_problem(&mut rng);
+                self.rng = rng;
+                (lang, ans)
+            }
+        };
+        self.problems.push((lang, ans.clone()));
+        (ans, lang)
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct ContestData {
     pub time: Vec<f32>,
+    /// Points of each word in modes scored by word length instead of time, such as the anagram hunt.
+    pub points: Vec<u32>,
+    /// Id of the player, whom the rating is recorded for.
+    pub user: Option<u64>,
 }
 
 impl ContestData {
     pub fn as_string(&self) -> String {
+        if !self.points.is_empty() {
+            return format!(
+                "{} pts, {} words",
+                self.points.iter().sum::<u32>(),
+                self.points.len()
+            );
+        }
+        if self.time.is_empty() {
+            return "0 AC".to_string();
+        }
         format!(
             "{} AC, average speed = {:.3} sec",
             self.time.len(),
@@ -267,10 +603,21 @@ impl ContestData {
     }
 
     pub fn key(&self) -> (i32, u32) {
-        (
-            -(self.time.len() as i32),
-            (self.time.iter().map(|x| x * 1000.0).sum::<f32>() / self.time.len() as f32) as u32,
-        )
+        if self.time.is_empty() {
+            return (0, 0);
+        }
+        let speed =
+            (self.time.iter().map(|x| x * 1000.0).sum::<f32>() / self.time.len() as f32) as u32;
+        if !self.points.is_empty() {
+            return (-(self.points.iter().sum::<u32>() as i32), speed);
+        }
+        (-(self.time.len() as i32), speed)
+    }
+
+    /// Records a word worth `points`, found `elapsed` seconds after the start.
+    pub fn add_word(&mut self, elapsed: f32, points: u32) {
+        self.time.push(elapsed);
+        self.points.push(points);
     }
 }
 
@@ -280,11 +627,46 @@ impl AddAssign<f32> for ContestData {
     }
 }
 
-pub fn aggregates(contest_result: &IndexMap<String, ContestData>) -> String {
-    use ordinal::Ordinal;
+impl AddAssign<&ContestData> for ContestData {
+    fn add_assign(&mut self, rhs: &ContestData) {
+        self.time.extend_from_slice(&rhs.time);
+        self.points.extend_from_slice(&rhs.points);
+    }
+}
+
+/// The result of `user` in the contest, which remembers the id of the user.
+pub fn contest_data<'a>(
+    contest_result: &'a mut IndexMap<String, ContestData>,
+    user: &User,
+) -> &'a mut ContestData {
+    let data = contest_result.entry(user.name.clone()).or_default();
+    data.user = Some(*user.id.as_u64());
+    data
+}
+
+/// Sorts the contest result, best first. `participants` who solved nothing are included.
+pub fn standings(
+    contest_result: &IndexMap<String, ContestData>,
+    participants: &[String],
+) -> Vec<(String, ContestData)> {
+    let mut contest_result = contest_result.clone();
+    for name in participants {
+        contest_result.entry(name.clone()).or_default();
+    }
     contest_result
-        .iter()
+        .into_iter()
         .sorted_by_key(|(_, data)| data.key())
+        .collect()
+}
+
+/// Ranks the contest result. `participants` who solved nothing are listed with zero solves.
+pub fn aggregates(
+    contest_result: &IndexMap<String, ContestData>,
+    participants: &[String],
+) -> String {
+    use ordinal::Ordinal;
+    standings(contest_result, participants)
+        .iter()
         .enumerate()
         .map(|(index, (name, data))| {
             format!(
@@ -297,10 +679,80 @@ pub fn aggregates(contest_result: &IndexMap<String, ContestData>) -> String {
         .collect::<String>()
 }
 
+/// Players registered for a contest held with a registration phase, or for a survival game.
+#[derive(Default, Debug)]
+pub struct Entry {
+    pub players: IndexMap<u64, String>,
+    /// Late joins are accepted until this problem is asked.
+    pub late_until: u32,
+    pub teams: Option<Teams>,
+    /// Starts a survival game instead of a contest when the entry closes.
+    pub survival: Option<SurvivalOption>,
+    /// Puzzle of the contest problems.
+    pub puzzle: puzzle::Kind,
+}
+
+/// Whether `user` may answer the current contest.
+/// Anyone may, unless the contest is held with registration.
+pub fn is_participant(user: u64) -> bool {
+    ENTRY
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map_or(true, |entry| entry.players.contains_key(&user))
+}
+
+/// Ends the contest, updates ratings and returns its final ranking.
+pub fn close_contest(
+    quiz: &mut Status,
+    contest_result: &mut IndexMap<String, ContestData>,
+) -> String {
+    let entry = ENTRY.lock().unwrap().take();
+    let participants = entry
+        .as_ref()
+        .map(|entry| entry.players.values().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    let mut ranking = aggregates(contest_result, &participants);
+    if let Some(Entry {
+        players,
+        teams: Some(teams),
+        ..
+    }) = &entry
+    {
+        ranking += &format!(
+            "\nチーム順位\n{}",
+            teams.aggregates(players, contest_result)
+        );
+    }
+    // participants who solved nothing are only known by the entry.
+    let rated = standings(contest_result, &participants)
+        .into_iter()
+        .filter_map(|(name, data)| {
+            let user = data.user.or_else(|| {
+                entry.as_ref().and_then(|entry| {
+                    entry
+                        .players
+                        .iter()
+                        .find(|(_, player)| **player == name)
+                        .map(|(id, _)| *id)
+                })
+            })?;
+            Some((user, data))
+        })
+        .collect::<Vec<_>>();
+    if let Err(why) = rating::update(&rated) {
+        println!("{}", why);
+    }
+    *contest_result = IndexMap::new();
+    *quiz = Status::StandingBy;
+    ranking
+}
+
 lazy_static! {
     pub static ref QUIZ: Arc<Mutex<Status>> = Arc::new(Mutex::new(Status::StandingBy));
     pub static ref CONTEST_RESULT: Arc<Mutex<IndexMap<String, ContestData>>> =
         Arc::new(Mutex::new(IndexMap::new()));
     pub static ref CONTEST_LIBRARY: Arc<Mutex<DictionarySelector>> =
         Arc::new(Mutex::new(DictionarySelector::new()));
+    pub static ref ENTRY: Arc<Mutex<Option<Entry>>> = Arc::new(Mutex::new(None));
 }
@@ -1,17 +1,41 @@
-use serenity::{model::channel::Message, prelude::*};
+use serenity::{
+    http::Http,
+    model::{channel::Message, id::ChannelId},
+    prelude::*,
+};
 
 use super::super::bot;
-use super::super::bot::ContestData;
+use super::super::bot::DictionarySelector;
+use super::super::coop;
+use super::super::daily;
 use super::super::dictionary;
-use super::super::sort::Sorted;
-use indexmap::IndexMap;
+use super::super::double;
+use super::super::duel;
+use super::super::error::BotError;
+use super::super::history;
+use super::super::hunt;
+use super::super::letters;
+use super::super::practice;
+use super::super::problem_set::{ProblemSet, PROBLEM_SETS};
+use super::super::puzzle::{self, Puzzle};
+use super::super::shiritori;
+use super::super::survival;
 
 use crate::try_say;
 use std::fs::File;
 use std::io::{BufWriter, Write};
 use std::str::from_utf8;
+use std::sync::Arc;
+use std::time::Instant;
+use unicode_segmentation::UnicodeSegmentation;
 
-pub(crate) fn prob(ctx: &mut Context, msg: &Message, lang: bot::Lang) -> String {
+pub(crate) fn prob(
+    ctx: &mut Context,
+    msg: &Message,
+    lang: bot::Lang,
+    kind: puzzle::Kind,
+    tag: Option<&str>,
+) -> (String, Box<dyn Puzzle>) {
     let dic = match lang {
         bot::Lang::En => &*dictionary::ENGLISH,
         bot::Lang::Ja => &*dictionary::JAPANESE,
@@ -21,18 +45,177 @@ pub(crate) fn prob(ctx: &mut Context, msg: &Message, lang: bot::Lang) -> String
         bot::Lang::Ru => &*dictionary::RUSSIAN,
         bot::Lang::Eo => &*dictionary::ESPERANTO,
     };
-    let ans = dic.get(&mut rand::thread_rng());
-    let sorted = ans.sorted();
+    let rng = &mut rand::thread_rng();
+    let min_length = kind.min_length();
+    let ans = dic
+        .get_tagged_except(rng, tag, |word| {
+            UnicodeSegmentation::graphemes(word, true).count() < min_length
+        })
+        .or_else(|| dic.get_tagged(rng, tag))
+        .unwrap_or_else(|| dic.get(rng));
+    let puzzle = kind.make(ans, dic, rng);
+    let sorted = puzzle.display(ans);
     try_say!(
         ctx,
         msg,
         format!(
-            "ソートなぞなぞ ソート前の {as_str} な〜んだ？\n`{prob}`",
+            "ソートなぞなぞ {statement} {as_str} な〜んだ？\n`{prob}`",
+            statement = kind.statement(),
             as_str = lang.as_symbol(),
             prob = sorted
         ));
     println!("called prob: [{}, {}]", ans, sorted);
-    ans.clone()
+    if let Err(why) = history::record(*msg.channel_id.as_u64(), lang, ans) {
+        println!("{}", why);
+    }
+    (ans.to_string(), puzzle)
+}
+
+/// Sets up the contest selector to draw problems at random.
+pub(crate) fn set_contest_library(
+    library: &mut DictionarySelector,
+    channel: ChannelId,
+    languages: Vec<(String, u32)>,
+    quota: bool,
+    fresh: Option<u32>,
+    seed: Option<u64>,
+    tag: Option<&str>,
+) {
+    library.set(languages, quota, seed.unwrap_or_else(rand::random), tag);
+    if let Some(days) = fresh {
+        library.avoid(
+            history::HISTORY
+                .lock()
+                .unwrap()
+                .recent(*channel.as_u64(), days),
+        );
+    }
+}
+
+/// Posts the first problem of a contest whose selector is already set up.
+pub(crate) fn start_contest(
+    http: impl AsRef<Http>,
+    channel: ChannelId,
+    quiz: &mut bot::Status,
+    library: &mut DictionarySelector,
+    num: u32,
+    kind: puzzle::Kind,
+) {
+    let (ans, lang) = library.next();
+    let puzzle = kind.make(&ans, bot::get_dictionary(lang), &mut library.puzzle_rng(1));
+    if let Err(why) = history::record(*channel.as_u64(), lang, &ans) {
+        println!("{}", why);
+    }
+    if let Err(why) = channel.say(
+        http,
+        format!(
+            "{number}問のコンテストを始めます。 (seed: {seed})\n問 1 (1/{number})\nソートなぞなぞ {statement} {symbol} な〜んだ？\n`{prob}`",
+            number = num,
+            seed = library.seed(),
+            statement = kind.statement(),
+            prob = puzzle.display(&ans),
+            symbol = lang.as_symbol(),
+        ),
+    ) {
+        println!("{}", why);
+    }
+    *quiz = bot::Status::Contesting(ans, lang, (1, num), Instant::now(), puzzle);
+}
+
+/// Closes the registration started at `recruited_at` and starts the contest,
+/// unless it has been cancelled meanwhile.
+pub(crate) fn close_entry(http: &Arc<Http>, channel: ChannelId, recruited_at: Instant) {
+    let quiz = &mut *bot::QUIZ.lock().unwrap();
+    let num = match quiz {
+        bot::Status::Recruiting(num, instant) if *instant == recruited_at => *num,
+        _ => return,
+    };
+    let survival = bot::ENTRY
+        .lock()
+        .unwrap()
+        .as_mut()
+        .and_then(|entry| entry.survival.take());
+    if let Some(option) = survival {
+        let players = bot::ENTRY.lock().unwrap().take().unwrap().players;
+        survival::start(http, channel, quiz, players, option);
+        return;
+    }
+    let (players, teams) = match bot::ENTRY.lock().unwrap().as_mut() {
+        Some(bot::Entry {
+            players,
+            teams: Some(teams),
+            ..
+        }) => {
+            teams.balance(players);
+            (
+                players.values().cloned().collect::<Vec<_>>(),
+                Some(teams.as_string(players)),
+            )
+        }
+        Some(entry) => (entry.players.values().cloned().collect::<Vec<_>>(), None),
+        None => (Vec::new(), None),
+    };
+    if players.is_empty() {
+        if let Err(why) = channel.say(&http, "参加者がいないためコンテストを中止します。")
+        {
+            println!("{}", why);
+        }
+        *bot::ENTRY.lock().unwrap() = None;
+        *quiz = bot::Status::StandingBy;
+        return;
+    }
+    if let Err(why) = channel.say(
+        &http,
+        match teams {
+            Some(teams) => format!("エントリーを締め切りました。\n{}", teams),
+            None => format!(
+                "エントリーを締め切りました。\n参加者: {}",
+                players.join(", ")
+            ),
+        },
+    ) {
+        println!("{}", why);
+    }
+    let kind = bot::ENTRY
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map_or_else(puzzle::Kind::default, |entry| entry.puzzle);
+    start_contest(
+        &http,
+        channel,
+        quiz,
+        &mut bot::CONTEST_LIBRARY.lock().unwrap(),
+        num,
+        kind,
+    );
+}
+
+/// Masks all but `num` randomly chosen graphemes of the answer with `*`.
+pub(crate) fn random_hint<Engine: rand::Rng>(
+    graphemes: &[&str],
+    num: usize,
+    rng: &mut Engine,
+) -> String {
+    let mut hint: Vec<&str> = std::iter::repeat("*").take(graphemes.len()).collect();
+    for idx in rand::seq::index::sample(rng, graphemes.len(), num).into_iter() {
+        hint[idx] = graphemes[idx];
+    }
+    hint.join("")
+}
+
+/// Reads a problem set from the file attached to `msg`,
+/// or else the one its author registered via DM.
+pub(crate) fn load_problem_set(msg: &Message) -> Result<ProblemSet, BotError> {
+    match msg.attachments.first() {
+        Some(attachment) => ProblemSet::parse(&attachment.download()?),
+        None => PROBLEM_SETS
+            .lock()
+            .unwrap()
+            .get(msg.author.id.as_u64())
+            .cloned()
+            .ok_or(BotError::NoProblemSet),
+    }
 }
 
 pub(crate) fn kick(ctx: &mut Context, msg: &Message) -> std::io::Result<()> {
@@ -67,7 +250,45 @@ fn main() {{
 }
 
 pub(crate) fn answer_check(ctx: &mut Context, msg: &Message) {
+    // quizzes are held in channels, so DMs are only for practices and the daily puzzle.
+    if msg.is_private() {
+        if !practice::answer_check(ctx, msg) {
+            let practicing = practice::is_practicing(*msg.author.id.as_u64());
+            daily::answer_check(ctx, msg, practicing);
+        }
+        return;
+    }
+    if duel::answer_check(&ctx.http, msg) {
+        return;
+    }
     if let Ok(mut quiz_guard) = bot::QUIZ.lock() {
+        if quiz_guard.is_contesting() && !bot::is_participant(*msg.author.id.as_u64()) {
+            return;
+        }
+        if quiz_guard.is_surviving() {
+            survival::answer_check(ctx, msg, &mut quiz_guard);
+            return;
+        }
+        if quiz_guard.is_cooperating() {
+            coop::answer_check(ctx, msg, &mut quiz_guard);
+            return;
+        }
+        if quiz_guard.is_chaining() {
+            shiritori::answer_check(ctx, msg, &mut quiz_guard);
+            return;
+        }
+        if quiz_guard.is_lettering() {
+            letters::answer_check(ctx, msg, &mut quiz_guard);
+            return;
+        }
+        if quiz_guard.is_hunting() {
+            hunt::answer_check(ctx, msg, &mut quiz_guard);
+            return;
+        }
+        if quiz_guard.is_doubling() {
+            double::answer_check(ctx, msg, &mut quiz_guard);
+            return;
+        }
         let elapsed = quiz_guard.elapsed();
         match quiz_guard.answer_check(&msg.content) {
             bot::CheckResult::WA => {
@@ -80,10 +301,12 @@ pub(crate) fn answer_check(ctx: &mut Context, msg: &Message) {
                         ctx,
                         msg,
                         format!(
-                            "{} さん、正解です！\n正解は\"{}\"でした！ [{:.3} sec]",
+                            "{} さん、正解です！\n正解は\"{}\"でした！ [{:.3} sec]{}{}",
                             &msg.author.name,
                             quiz_guard.ans().unwrap(),
                             elapsed.unwrap(),
+                            quiz_guard.puzzle().unwrap().reveal(),
+                            quiz_guard.describe(),
                         )
                     );
                     *quiz_guard = bot::Status::StandingBy;
@@ -93,19 +316,18 @@ pub(crate) fn answer_check(ctx: &mut Context, msg: &Message) {
                         ctx,
                         msg,
                         format!(
-                            "{} さん、正解です！\n正解は\"{}\"でした！ [{:.3} sec]",
+                            "{} さん、正解です！\n正解は\"{}\"でした！ [{:.3} sec]{}",
                             &msg.author.name,
                             quiz_guard.ans().unwrap(),
                             elapsed.unwrap(),
+                            quiz_guard.describe(),
                         )
                     );
                     let contest_result = &mut *bot::CONTEST_RESULT.lock().unwrap();
 
-                    *contest_result
-                        .entry(msg.author.name.clone())
-                        .or_insert(ContestData::default()) += elapsed.unwrap();
+                    *bot::contest_data(contest_result, &msg.author) += elapsed.unwrap();
 
-                    let (_, num) = quiz_guard.get_contest_num().unwrap();
+                    let (_, &num) = quiz_guard.get_contest_num().unwrap();
 
                     if quiz_guard.is_contest_end() {
                         try_say!(
@@ -114,22 +336,17 @@ pub(crate) fn answer_check(ctx: &mut Context, msg: &Message) {
                             format!(
                                 "{num}問連続のコンテストが終了しました。\n{result}",
                                 num = num,
-                                result = bot::aggregates(dbg!(&*contest_result))
+                                result = bot::close_contest(&mut quiz_guard, contest_result)
                             )
                         );
-                        *contest_result = IndexMap::new();
-                        *quiz_guard = bot::Status::StandingBy;
                     } else {
                         quiz_guard.contest_continue(ctx, msg);
                     }
                 }
             }
             bot::CheckResult::Anagram(ans) => {
-                *bot::CONTEST_RESULT
-                    .lock()
-                    .unwrap()
-                    .entry(msg.author.name.clone())
-                    .or_insert(ContestData::default()) += elapsed.unwrap();
+                *bot::contest_data(&mut bot::CONTEST_RESULT.lock().unwrap(), &msg.author) +=
+                    elapsed.unwrap();
                 try_say!(
                     ctx,
                     msg,
@@ -141,11 +358,8 @@ pub(crate) fn answer_check(ctx: &mut Context, msg: &Message) {
                 );
             }
             bot::CheckResult::Full(ans) => {
-                *bot::CONTEST_RESULT
-                    .lock()
-                    .unwrap()
-                    .entry(msg.author.name.clone())
-                    .or_insert(ContestData::default()) += elapsed.unwrap();
+                *bot::contest_data(&mut bot::CONTEST_RESULT.lock().unwrap(), &msg.author) +=
+                    elapsed.unwrap();
                 try_say!(
                     ctx,
                     msg,
@@ -10,19 +10,30 @@ use serenity::{
 use std::time::Instant;
 
 use super::super::bot;
+use super::super::coop;
+use super::super::daily;
+use super::super::double;
+use super::super::duel;
 use super::super::error::BotError;
+use super::super::hunt;
+use super::super::letters;
+use super::super::practice;
+use super::super::puzzle;
+use super::super::review;
+use super::super::schedule;
 use super::super::settings;
-use super::super::sort::Sorted;
+use super::super::shiritori;
+use super::super::stats;
+use super::super::team::{TeamMode, Teams};
+use super::super::tournament;
 use super::{executors, parser};
 use crate::bot::ContestData;
 use crate::try_say;
 use indexmap::IndexMap;
+use itertools::Itertools;
 use serenity::framework::standard::{help_commands, CommandGroup, HelpOptions};
 use serenity::model::id::UserId;
 use std::collections::HashSet;
-use std::fs::OpenOptions;
-use std::io::Write;
-use unicode_segmentation::UnicodeSegmentation;
 
 macro_rules! count {
     ( $x:ident ) => (1usize);
@@ -71,7 +82,7 @@ group!({
     options: {
         description: "A group with commands providing hint and giveup.",
     },
-    commands: [giveup, hint],
+    commands: [giveup, hint, double, daily, practice, review],
 });
 
 group!({
@@ -79,7 +90,31 @@ group!({
     options: {
         description: "A group with commands providing contest mode.",
     },
-    commands: [contest, unrated],
+    commands: [contest, survival, coop, shiritori, hunt, unrated, export, problemset, join, leave],
+});
+
+group!({
+    name: "duel",
+    options: {
+        description: "A group with commands providing head-to-head duels.",
+    },
+    commands: [duel, accept, stats],
+});
+
+group!({
+    name: "tournament",
+    options: {
+        description: "A group with commands providing tournaments of duels.",
+    },
+    commands: [tournament, bracket],
+});
+
+group!({
+    name: "letters",
+    options: {
+        description: "A group with commands providing letters rounds, where the longest word made of drawn letters wins.",
+    },
+    commands: [letters, v, c],
 });
 
 group!({
@@ -87,111 +122,140 @@ group!({
     options: {
         description: "A group with commands providing settings of enable/disable switch in channel.",
     },
-    commands: [enable, disable],
+    commands: [enable, disable, puzzle, tags],
 });
 
+/// Number of words reviewed by `~review start` without a number.
+const REVIEW_DEFAULT_NUM: usize = 20;
+
+/// The puzzle given to a quiz command, or else the channel default, and the tag given to it.
+fn quiz_option(
+    ctx: &mut Context,
+    msg: &Message,
+    args: &mut Args,
+    lang: bot::Lang,
+) -> Option<(puzzle::Kind, Option<String>)> {
+    match parser::quiz(args, lang) {
+        Ok(parser::QuizOption { puzzle, tag }) => Some((
+            puzzle.unwrap_or_else(|| puzzle::channel_default(*msg.channel_id.as_u64())),
+            tag,
+        )),
+        Err(err_msg) => {
+            try_say!(ctx, msg, err_msg);
+            None
+        }
+    }
+}
+
 #[command]
-#[description = "Provides a quiz of English as response."]
+#[description = "Provides a quiz of English as response. `~en [puzzle] [--tag <tag>]`"]
 #[bucket = "basic"]
-pub fn en(ctx: &mut Context, msg: &Message) -> CommandResult {
+pub fn en(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
     println!("Got command '~en' by user '{}'", msg.author.name);
     if_chain! {
         if !msg.author.bot;
+        if let Some((kind, tag)) = quiz_option(ctx, msg, &mut args, bot::Lang::En);
         if let Ok(mut guard) = bot::QUIZ.lock();
         then {
-            let ans = executors::prob(ctx, &msg, bot::Lang::En);
-            *guard = bot::Status::Holding(ans, bot::Lang::En, Instant::now());
+            let (ans, puzzle) = executors::prob(ctx, &msg, bot::Lang::En, kind, tag.as_deref());
+            *guard = bot::Status::Holding(ans, bot::Lang::En, Instant::now(), puzzle);
         }
     }
     Ok(())
 }
 
 #[command]
-#[description = "Provides a quiz of Japanese as response."]
+#[description = "Provides a quiz of Japanese as response. `~ja [puzzle] [--tag <tag>]`"]
 #[bucket = "basic"]
-pub fn ja(ctx: &mut Context, msg: &Message) -> CommandResult {
+pub fn ja(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
     println!("Got command '~ja' by user '{}'", msg.author.name);
     if_chain! {
         if !msg.author.bot;
+        if let Some((kind, tag)) = quiz_option(ctx, msg, &mut args, bot::Lang::Ja);
         if let Ok(mut guard) = bot::QUIZ.lock();
         then {
-            let ans = executors::prob(ctx, &msg, bot::Lang::Ja);
-            *guard = bot::Status::Holding(ans, bot::Lang::Ja, Instant::now());
+            let (ans, puzzle) = executors::prob(ctx, &msg, bot::Lang::Ja, kind, tag.as_deref());
+            *guard = bot::Status::Holding(ans, bot::Lang::Ja, Instant::now(), puzzle);
         }
     }
     Ok(())
 }
 #[command]
-#[description = "Provides a quiz of French as response."]
+#[description = "Provides a quiz of French as response. `~fr [puzzle] [--tag <tag>]`"]
 #[bucket = "basic"]
-pub fn fr(ctx: &mut Context, msg: &Message) -> CommandResult {
+pub fn fr(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
     println!("Got command '~fr' by user '{}'", msg.author.name);
     if_chain! {
         if !msg.author.bot;
+        if let Some((kind, tag)) = quiz_option(ctx, msg, &mut args, bot::Lang::Fr);
         if let Ok(mut guard) = bot::QUIZ.lock();
         then {
-            let ans = executors::prob(ctx, &msg, bot::Lang::Fr);
-            *guard = bot::Status::Holding(ans, bot::Lang::Fr, Instant::now());
+            let (ans, puzzle) = executors::prob(ctx, &msg, bot::Lang::Fr, kind, tag.as_deref());
+            *guard = bot::Status::Holding(ans, bot::Lang::Fr, Instant::now(), puzzle);
         }
     }
     Ok(())
 }
 #[command]
-#[description = "Provides a quiz of German as response."]
+#[description = "Provides a quiz of German as response. `~de [puzzle] [--tag <tag>]`"]
 #[bucket = "basic"]
-pub fn de(ctx: &mut Context, msg: &Message) -> CommandResult {
+pub fn de(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
     println!("Got command '~de' by user '{}'", msg.author.name);
     if_chain! {
         if !msg.author.bot;
+        if let Some((kind, tag)) = quiz_option(ctx, msg, &mut args, bot::Lang::De);
         if let Ok(mut guard) = bot::QUIZ.lock();
         then {
-            let ans = executors::prob(ctx, &msg, bot::Lang::De);
-            *guard = bot::Status::Holding(ans, bot::Lang::De, Instant::now());
+            let (ans, puzzle) = executors::prob(ctx, &msg, bot::Lang::De, kind, tag.as_deref());
+            *guard = bot::Status::Holding(ans, bot::Lang::De, Instant::now(), puzzle);
         }
     }
     Ok(())
 }
 #[command]
-#[description = "Provides a quiz of Italian as response."]
+#[description = "Provides a quiz of Italian as response. `~it [puzzle] [--tag <tag>]`"]
 #[bucket = "basic"]
-pub fn it(ctx: &mut Context, msg: &Message) -> CommandResult {
+pub fn it(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
     println!("Got command '~it' by user '{}'", msg.author.name);
     if_chain! {
         if !msg.author.bot;
+        if let Some((kind, tag)) = quiz_option(ctx, msg, &mut args, bot::Lang::It);
         if let Ok(mut guard) = bot::QUIZ.lock();
         then {
-            let ans = executors::prob(ctx, &msg, bot::Lang::It);
-            *guard = bot::Status::Holding(ans, bot::Lang::It, Instant::now());
+            let (ans, puzzle) = executors::prob(ctx, &msg, bot::Lang::It, kind, tag.as_deref());
+            *guard = bot::Status::Holding(ans, bot::Lang::It, Instant::now(), puzzle);
         }
     }
     Ok(())
 }
 #[command]
-#[description = "Provides a quiz of Russian as response."]
+#[description = "Provides a quiz of Russian as response. `~ru [puzzle] [--tag <tag>]`"]
 #[bucket = "basic"]
-pub fn ru(ctx: &mut Context, msg: &Message) -> CommandResult {
+pub fn ru(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
     println!("Got command '~ru' by user '{}'", msg.author.name);
     if_chain! {
         if !msg.author.bot;
+        if let Some((kind, tag)) = quiz_option(ctx, msg, &mut args, bot::Lang::Ru);
         if let Ok(mut guard) = bot::QUIZ.lock();
         then {
-            let ans = executors::prob(ctx, &msg, bot::Lang::Ru);
-            *guard = bot::Status::Holding(ans, bot::Lang::Ru, Instant::now());
+            let (ans, puzzle) = executors::prob(ctx, &msg, bot::Lang::Ru, kind, tag.as_deref());
+            *guard = bot::Status::Holding(ans, bot::Lang::Ru, Instant::now(), puzzle);
         }
     }
     Ok(())
 }
 #[command]
-#[description = "Provides a quiz of Esperanto as response."]
+#[description = "Provides a quiz of Esperanto as response. `~eo [puzzle] [--tag <tag>]`"]
 #[bucket = "basic"]
-pub fn eo(ctx: &mut Context, msg: &Message) -> CommandResult {
+pub fn eo(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
     println!("Got command '~eo' by user '{}'", msg.author.name);
     if_chain! {
         if !msg.author.bot;
+        if let Some((kind, tag)) = quiz_option(ctx, msg, &mut args, bot::Lang::Eo);
         if let Ok(mut guard) = bot::QUIZ.lock();
         then {
-            let ans = executors::prob(ctx, &msg, bot::Lang::Eo);
-            *guard = bot::Status::Holding(ans, bot::Lang::Eo, Instant::now());
+            let (ans, puzzle) = executors::prob(ctx, &msg, bot::Lang::Eo, kind, tag.as_deref());
+            *guard = bot::Status::Holding(ans, bot::Lang::Eo, Instant::now(), puzzle);
         }
     }
     Ok(())
@@ -199,13 +263,30 @@ pub fn eo(ctx: &mut Context, msg: &Message) -> CommandResult {
 
 fn giveup_impl(ctx: &mut Context, msg: &Message, quiz_stat: &mut bot::Status) -> CommandResult {
     if !msg.author.bot {
-        if quiz_stat.is_standing_by() {
+        if quiz_stat.is_standing_by() || quiz_stat.is_recruiting() {
             try_say!(ctx, msg, "現在問題は出ていません。");
+        } else if quiz_stat.is_surviving() {
+            try_say!(ctx, msg, "サバイバル中はギブアップできません。");
+        } else if let bot::Status::Cooperating(coop) = quiz_stat {
+            coop::giveup(ctx, msg, coop);
+        } else if quiz_stat.is_chaining() {
+            shiritori::giveup(ctx, msg, quiz_stat);
+        } else if quiz_stat.is_lettering() {
+            letters::giveup(ctx, msg, quiz_stat);
+        } else if quiz_stat.is_hunting() {
+            hunt::giveup(ctx, msg, quiz_stat);
+        } else if quiz_stat.is_doubling() {
+            double::giveup(ctx, msg, quiz_stat);
         } else if quiz_stat.is_holding() {
             try_say!(
                 ctx,
                 msg,
-                format!("正解は \"{}\" でした...", quiz_stat.ans().unwrap())
+                format!(
+                    "正解は \"{}\" でした...{}{}",
+                    quiz_stat.ans().unwrap(),
+                    quiz_stat.puzzle().unwrap().reveal(),
+                    quiz_stat.describe()
+                )
             );
             *quiz_stat = bot::Status::StandingBy;
         } else {
@@ -217,30 +298,115 @@ fn giveup_impl(ctx: &mut Context, msg: &Message, quiz_stat: &mut bot::Status) ->
                 try_say!(
                     ctx,
                     msg,
-                    format!("正解は \"{}\" でした...", quiz_stat.ans().unwrap())
+                    format!(
+                        "正解は \"{}\" でした...{}",
+                        quiz_stat.ans().unwrap(),
+                        quiz_stat.describe()
+                    )
                 );
                 quiz_stat.contest_continue(ctx, &msg);
             } else {
-                let (_, num) = quiz_stat.get_contest_num().unwrap();
+                let (_, &num) = quiz_stat.get_contest_num().unwrap();
+                let ans = quiz_stat.ans().unwrap().clone();
+                let description = quiz_stat.describe();
                 msg.channel_id
                     .say(
                         &ctx,
                         format!(
-                            "正解は \"{ans}\" でした...\n{num}問連続のコンテストが終了しました。\n{result}",
-                            ans = quiz_stat.ans().unwrap(),
+                            "正解は \"{ans}\" でした...{description}\n{num}問連続のコンテストが終了しました。\n{result}",
+                            ans = ans,
+                            description = description,
                             num = num,
-                            result = bot::aggregates(contest_result)
+                            result = bot::close_contest(quiz_stat, contest_result)
                         ),
                     )
                     .expect("fail to post");
-                *contest_result = IndexMap::new();
-                *quiz_stat = bot::Status::StandingBy;
             }
         }
     }
     Ok(())
 }
 
+#[command]
+#[description = "Lists the tags of a language with the number of their words. `~tags <lang>`"]
+#[bucket = "basic"]
+pub fn tags(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    println!("Got command '~tags' by user '{}'", msg.author.name);
+    let lang = match args
+        .single::<String>()
+        .ok()
+        .and_then(|code| bot::Lang::from_code(&code))
+    {
+        Some(lang) => lang,
+        None => {
+            try_say!(ctx, msg, "使い方: `~tags <言語>` (例: `~tags en`)");
+            return Ok(());
+        }
+    };
+    let tags = bot::get_dictionary(lang).tags();
+    if tags.is_empty() {
+        try_say!(
+            ctx,
+            msg,
+            format!("{} の辞書にタグはありません。", lang.as_symbol())
+        );
+    } else {
+        try_say!(
+            ctx,
+            msg,
+            format!(
+                "{} のタグ:\n{}",
+                lang.as_symbol(),
+                tags.iter()
+                    .map(|(tag, len)| format!("{} ({})", tag, len))
+                    .join(", ")
+            )
+        );
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Shows or sets the puzzle of quizzes and contests in this channel. `~puzzle [name]`"]
+#[bucket = "basic"]
+pub fn puzzle(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    println!("Got command '~puzzle' by user '{}'", msg.author.name);
+    if msg.author.bot {
+        return Ok(());
+    }
+    let channel = *msg.channel_id.as_u64();
+    match parser::puzzle(&mut args) {
+        Err(err_msg) => {
+            try_say!(ctx, msg, err_msg);
+        }
+        Ok(None) => {
+            try_say!(
+                ctx,
+                msg,
+                format!(
+                    "このチャンネルの出題形式: {}\n選べる形式: {}",
+                    puzzle::channel_default(channel).name(),
+                    puzzle::Kind::NAMES.join(", ")
+                )
+            );
+        }
+        Ok(Some(kind)) => {
+            settings::SETTINGS
+                .lock()
+                .unwrap()
+                .puzzles
+                .insert(channel.to_string(), kind);
+            sync_setting()?;
+            try_say!(
+                ctx,
+                msg,
+                format!("このチャンネルの出題形式を {} にしました。", kind.name())
+            );
+        }
+    }
+    Ok(())
+}
+
 #[command]
 #[description = "Allows to give up current quiz and shows answer as response."]
 #[bucket = "basic"]
@@ -254,45 +420,733 @@ pub fn giveup(ctx: &mut Context, msg: &Message) -> CommandResult {
 }
 
 #[command]
-#[description = "Starts contest mode."]
+#[description = "Starts contest mode. `~contest team` holds a team contest. `~contest schedule`, `~contest list` and `~contest cancel` manage scheduled contests."]
 #[bucket = "long"]
 pub fn contest(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
     use crate::bot::CONTEST_LIBRARY;
     println!("Got command '~contest' by user '{}'", msg.author.name);
+    match parser::contest_subcommand(&msg.content) {
+        Some("schedule") => return schedule_contest(ctx, msg, args.advance()),
+        Some("list") => return list_schedule(ctx, msg),
+        Some("cancel") => return cancel_schedule(ctx, msg, args.advance()),
+        _ => {}
+    }
     if_chain! {
         if !msg.author.bot;
         if let Ok(mut quiz_guard) = bot::QUIZ.lock();
         if quiz_guard.is_standing_by();
         then {
-            match parser::contest(&mut args) {
+            let parsed = if args.current() == Some("team") {
+                args.advance();
+                parser::team_contest(&mut args).map(|(contest, team)| (contest, Some(team)))
+            } else {
+                parser::contest(&mut args).map(|contest| (contest, None))
+            };
+            match parsed {
                 Err(err_msg) => {
                     try_say!(ctx,msg,err_msg);
                     return Ok(());
                 }
-                Ok((num, mut languages)) => {
-                    languages.sort();
-                    languages.dedup();
-                    CONTEST_LIBRARY.lock().unwrap().set(languages);
-                    let (dic, lang) = CONTEST_LIBRARY
-                        .lock()
-                        .unwrap()
-                        .select(&mut rand::thread_rng());
-                    let ans = dic.get(&mut rand::thread_rng());
-                    msg.channel_id
-                        .say(
-                            &ctx,
-                            format!(
-                                "{number}問のコンテストを始めます。\n問 1 (1/{number})\nソートなぞなぞ ソート前の {symbol} な〜んだ？\n`{prob}`",
-                                number = num,
-                                prob = ans.sorted(),
-                                symbol = lang.as_symbol(),
-                            ),
+                Ok((parser::ContestOption { num, languages, quota, fresh, seed, problem_set, shuffle, entry, late, puzzle, tag }, team)) => {
+                    let mode = match team {
+                        None => None,
+                        Some(parser::TeamOption { by: parser::TeamBy::Join, .. }) => Some(TeamMode::Join),
+                        Some(parser::TeamOption { by: parser::TeamBy::Auto, teams }) => Some(TeamMode::Auto(teams)),
+                        Some(parser::TeamOption { by: parser::TeamBy::Role, .. }) => {
+                            if msg.mention_roles.is_empty() {
+                                try_say!(ctx, msg, "`--by role` ではチームにするロールをメンションしてください。");
+                                return Ok(());
+                            }
+                            Some(TeamMode::Role(msg.mention_roles.iter().map(|role| *role.as_u64()).collect()))
+                        }
+                    };
+                    let kind = puzzle.unwrap_or_else(|| puzzle::channel_default(*msg.channel_id.as_u64()));
+                    let mut library = CONTEST_LIBRARY.lock().unwrap();
+                    let num = if problem_set {
+                        let problem_set = match executors::load_problem_set(msg) {
+                            Ok(problem_set) => problem_set,
+                            Err(why) => {
+                                try_say!(ctx, msg, format!("問題セットを読み込めませんでした: {}", why));
+                                return Ok(());
+                            }
+                        };
+                        let problems = match problem_set.problems() {
+                            Ok(problems) => problems,
+                            Err(why) => {
+                                try_say!(ctx, msg, format!("問題セットを読み込めませんでした: {}", why));
+                                return Ok(());
+                            }
+                        };
+                        let len = problems.len() as u32;
+                        library.preset(
+                            problems,
+                            seed.or(problem_set.seed).unwrap_or_else(rand::random),
+                            shuffle || problem_set.shuffle,
+                        );
+                        num.map_or(len, |num| num.min(len))
+                    } else {
+                        executors::set_contest_library(
+                            &mut library,
+                            msg.channel_id,
+                            languages,
+                            quota,
+                            fresh,
+                            seed,
+                            tag.as_deref(),
+                        );
+                        num.unwrap()
+                    };
+                    match entry {
+                        None => {
+                            *bot::ENTRY.lock().unwrap() = None;
+                            executors::start_contest(&ctx, msg.channel_id, &mut quiz_guard, &mut library, num, kind);
+                        }
+                        Some(secs) => {
+                            let how = match &mode {
+                                None => "`~join` でエントリーしてください。",
+                                Some(TeamMode::Join) => "チーム戦です。`~join <チーム名>` でチームを選んでエントリーしてください。",
+                                Some(TeamMode::Role(_)) => "チーム戦です。`~join` でエントリーするとロールでチームが分けられます。",
+                                Some(TeamMode::Auto(_)) => "チーム戦です。`~join` でエントリーするとレートでチームが分けられます。",
+                            };
+                            *bot::ENTRY.lock().unwrap() = Some(bot::Entry {
+                                players: IndexMap::new(),
+                                late_until: late,
+                                teams: mode.map(Teams::new),
+                                survival: None,
+                                puzzle: kind,
+                            });
+                            let recruited_at = Instant::now();
+                            *quiz_guard = bot::Status::Recruiting(num, recruited_at);
+                            try_say!(
+                                ctx,
+                                msg,
+                                format!(
+                                    "{num}問のコンテストの参加者を募集します。\n{how}{secs} 秒後に開始します。",
+                                    num = num,
+                                    how = how,
+                                    secs = secs,
+                                )
+                            );
+                            let http = ctx.http.clone();
+                            let channel = msg.channel_id;
+                            std::thread::spawn(move || {
+                                std::thread::sleep(std::time::Duration::from_secs(u64::from(secs)));
+                                executors::close_entry(&http, channel, recruited_at);
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Starts survival mode, where players who cannot solve a round within its time limit are eliminated."]
+#[bucket = "long"]
+pub fn survival(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    println!("Got command '~survival' by user '{}'", msg.author.name);
+    if_chain! {
+        if !msg.author.bot;
+        if let Ok(mut quiz_guard) = bot::QUIZ.lock();
+        then {
+            if !quiz_guard.is_standing_by() {
+                try_say!(ctx, msg, "現在ほかのゲームが進行中です。");
+                return Ok(());
+            }
+            match parser::survival(&mut args) {
+                Err(err_msg) => {
+                    try_say!(ctx, msg, err_msg);
+                }
+                Ok(option) => {
+                    let secs = option.entry;
+                    *bot::ENTRY.lock().unwrap() = Some(bot::Entry {
+                        players: IndexMap::new(),
+                        late_until: 0,
+                        teams: None,
+                        survival: Some(option),
+                        puzzle: puzzle::Kind::default(),
+                    });
+                    let recruited_at = Instant::now();
+                    *quiz_guard = bot::Status::Recruiting(0, recruited_at);
+                    try_say!(
+                        ctx,
+                        msg,
+                        format!(
+                            "サバイバルの参加者を募集します。\n`~join` でエントリーしてください。{} 秒後に開始します。",
+                            secs
                         )
-                        .expect("fail to post");
-                    *quiz_guard = bot::Status::Contesting(ans.to_string(), lang, (1, num), Instant::now());
+                    );
+                    let http = ctx.http.clone();
+                    let channel = msg.channel_id;
+                    std::thread::spawn(move || {
+                        std::thread::sleep(std::time::Duration::from_secs(u64::from(secs)));
+                        executors::close_entry(&http, channel, recruited_at);
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Starts co-op mode, where the channel solves as many puzzles as possible within the time limit."]
+#[bucket = "long"]
+pub fn coop(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    println!("Got command '~coop' by user '{}'", msg.author.name);
+    if_chain! {
+        if !msg.author.bot;
+        if let Ok(mut quiz_guard) = bot::QUIZ.lock();
+        then {
+            if !quiz_guard.is_standing_by() {
+                try_say!(ctx, msg, "現在ほかのゲームが進行中です。");
+                return Ok(());
+            }
+            match parser::coop(&mut args) {
+                Err(err_msg) => {
+                    try_say!(ctx, msg, err_msg);
+                }
+                Ok(option) => {
+                    coop::start(&ctx.http, msg.channel_id, &mut quiz_guard, option);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Starts shiritori, where each puzzle starts with the last letter of the previous answer. `~shiritori [lang]`, Japanese by default."]
+#[bucket = "long"]
+pub fn shiritori(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    println!("Got command '~shiritori' by user '{}'", msg.author.name);
+    if_chain! {
+        if !msg.author.bot;
+        if let Ok(mut quiz_guard) = bot::QUIZ.lock();
+        then {
+            if !quiz_guard.is_standing_by() {
+                try_say!(ctx, msg, "現在ほかのゲームが進行中です。");
+                return Ok(());
+            }
+            let code = args.single::<String>().unwrap_or_else(|_| "ja".to_string());
+            match bot::Lang::from_code(&code) {
+                Some(lang) => shiritori::start(ctx, msg, &mut quiz_guard, lang),
+                None => try_say!(ctx, msg, format!("unexpected language '{}'.", code)),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Provides a puzzle of two words merged. `~double [lang] [easy|normal|hard]`, English and normal by default."]
+#[bucket = "basic"]
+pub fn double(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    println!("Got command '~double' by user '{}'", msg.author.name);
+    if_chain! {
+        if !msg.author.bot;
+        if let Ok(mut quiz_guard) = bot::QUIZ.lock();
+        then {
+            if !quiz_guard.is_standing_by() {
+                try_say!(ctx, msg, "現在ほかのゲームが進行中です。");
+                return Ok(());
+            }
+            let code = args.single::<String>().unwrap_or_else(|_| "en".to_string());
+            let difficulty = args.single::<String>().unwrap_or_else(|_| "normal".to_string());
+            match (bot::Lang::from_code(&code), double::Difficulty::from_name(&difficulty)) {
+                (None, _) => try_say!(ctx, msg, format!("unexpected language '{}'.", code)),
+                (_, None) => try_say!(ctx, msg, format!("unexpected difficulty '{}'.", difficulty)),
+                (Some(lang), Some(difficulty)) => {
+                    double::start(ctx, msg, &mut quiz_guard, lang, difficulty)
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Starts an anagram hunt, where players find as many words as possible from the letters of a word. `~hunt [lang] [seconds]`, English and 90 seconds by default."]
+#[bucket = "long"]
+pub fn hunt(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    println!("Got command '~hunt' by user '{}'", msg.author.name);
+    if_chain! {
+        if !msg.author.bot;
+        if let Ok(mut quiz_guard) = bot::QUIZ.lock();
+        then {
+            if !quiz_guard.is_standing_by() {
+                try_say!(ctx, msg, "現在ほかのゲームが進行中です。");
+                return Ok(());
+            }
+            let code = args.single::<String>().unwrap_or_else(|_| "en".to_string());
+            let secs = args.single::<u64>().unwrap_or(hunt::DEFAULT_SECS);
+            match bot::Lang::from_code(&code) {
+                Some(_) if secs == 0 => try_say!(ctx, msg, "制限時間は1秒以上にしてください。"),
+                Some(lang) => hunt::start(&ctx.http, msg.channel_id, &mut quiz_guard, lang, secs),
+                None => try_say!(ctx, msg, format!("unexpected language '{}'.", code)),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Starts a letters round, where players draw letters by `~v` and `~c` and then submit the longest word of them. `~letters [lang]`, English by default."]
+#[bucket = "long"]
+pub fn letters(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    println!("Got command '~letters' by user '{}'", msg.author.name);
+    if_chain! {
+        if !msg.author.bot;
+        if let Ok(mut quiz_guard) = bot::QUIZ.lock();
+        then {
+            if !quiz_guard.is_standing_by() {
+                try_say!(ctx, msg, "現在ほかのゲームが進行中です。");
+                return Ok(());
+            }
+            let code = args.single::<String>().unwrap_or_else(|_| "en".to_string());
+            match bot::Lang::from_code(&code) {
+                Some(lang) => letters::start(ctx, msg, &mut quiz_guard, lang),
+                None => try_say!(ctx, msg, format!("unexpected language '{}'.", code)),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Draws a vowel in a letters round."]
+pub fn v(ctx: &mut Context, msg: &Message) -> CommandResult {
+    println!("Got command '~v' by user '{}'", msg.author.name);
+    if_chain! {
+        if !msg.author.bot;
+        if let Ok(mut quiz_guard) = bot::QUIZ.lock();
+        then {
+            letters::draw(&ctx.http, msg.channel_id, &mut quiz_guard, letters::Kind::Vowel);
+        }
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Draws a consonant in a letters round."]
+pub fn c(ctx: &mut Context, msg: &Message) -> CommandResult {
+    println!("Got command '~c' by user '{}'", msg.author.name);
+    if_chain! {
+        if !msg.author.bot;
+        if let Ok(mut quiz_guard) = bot::QUIZ.lock();
+        then {
+            letters::draw(&ctx.http, msg.channel_id, &mut quiz_guard, letters::Kind::Consonant);
+        }
+    }
+    Ok(())
+}
+
+fn schedule_contest(ctx: &mut Context, msg: &Message, args: &mut Args) -> CommandResult {
+    match parser::schedule(args) {
+        Err(err_msg) => {
+            try_say!(ctx, msg, err_msg);
+        }
+        Ok(option) => {
+            let at = option.at;
+            let id = schedule::add(msg.channel_id, msg.author.id, option)?;
+            try_say!(
+                ctx,
+                msg,
+                format!(
+                    "コンテスト #{} を {} に予約しました。",
+                    id,
+                    schedule::format_datetime(at)
+                )
+            );
+        }
+    }
+    Ok(())
+}
+
+fn list_schedule(ctx: &mut Context, msg: &Message) -> CommandResult {
+    let schedule = schedule::SCHEDULE.lock().unwrap();
+    let contests = schedule.list(msg.channel_id);
+    if contests.is_empty() {
+        try_say!(ctx, msg, "予定されているコンテストはありません。");
+    } else {
+        try_say!(
+            ctx,
+            msg,
+            format!(
+                "予定されているコンテスト:\n{}",
+                contests
+                    .iter()
+                    .map(|contest| contest.as_string())
+                    .join("\n")
+            )
+        );
+    }
+    Ok(())
+}
+
+fn cancel_schedule(ctx: &mut Context, msg: &Message, args: &mut Args) -> CommandResult {
+    match args.single::<u32>() {
+        Err(_) => {
+            try_say!(
+                ctx,
+                msg,
+                "`~contest cancel <id>` の形式で指定してください。"
+            );
+        }
+        Ok(id) => match schedule::cancel(msg.channel_id, id, msg.author.id)? {
+            Some(contest) => {
+                try_say!(
+                    ctx,
+                    msg,
+                    format!(
+                        "コンテスト {} の予約を取り消しました。",
+                        contest.as_string()
+                    )
+                );
+            }
+            None => {
+                try_say!(
+                    ctx,
+                    msg,
+                    format!("予約 #{} が見つからないか、取り消す権限がありません。", id)
+                );
+            }
+        },
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Registers for the contest which is recruiting participants. `~join <team>` chooses the team of a team contest."]
+#[bucket = "basic"]
+pub fn join(ctx: &mut Context, msg: &Message, args: Args) -> CommandResult {
+    println!("Got command '~join' by user '{}'", msg.author.name);
+    let quiz = bot::QUIZ.lock().unwrap();
+    let mut entry = bot::ENTRY.lock().unwrap();
+    let accepting = match (&*quiz, entry.as_ref()) {
+        (bot::Status::Recruiting(..), Some(_)) => true,
+        (bot::Status::Contesting(_, _, (count, _), ..), Some(entry)) => *count <= entry.late_until,
+        _ => false,
+    };
+    match entry.as_mut() {
+        Some(entry) if accepting => {
+            let team = match entry.teams.as_mut() {
+                Some(teams) => {
+                    let roles = msg.member.as_ref().map_or(Vec::new(), |member| {
+                        member.roles.iter().map(|role| *role.as_u64()).collect()
+                    });
+                    match teams.join(*msg.author.id.as_u64(), args.current(), &roles) {
+                        Ok(team) => team,
+                        Err(why) => {
+                            try_say!(ctx, msg, why);
+                            return Ok(());
+                        }
+                    }
+                }
+                None => None,
+            };
+            entry
+                .players
+                .insert(*msg.author.id.as_u64(), msg.author.name.clone());
+            match team {
+                Some(team) => try_say!(
+                    ctx,
+                    msg,
+                    format!(
+                        "{} さんがチーム {} にエントリーしました。 (参加者 {} 人)",
+                        msg.author.name,
+                        team,
+                        entry.players.len()
+                    )
+                ),
+                None => try_say!(
+                    ctx,
+                    msg,
+                    format!(
+                        "{} さんがエントリーしました。 (参加者 {} 人)",
+                        msg.author.name,
+                        entry.players.len()
+                    )
+                ),
+            }
+        }
+        _ => {
+            try_say!(ctx, msg, "現在エントリーを受け付けていません。");
+        }
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Cancels the registration for the contest."]
+#[bucket = "basic"]
+pub fn leave(ctx: &mut Context, msg: &Message) -> CommandResult {
+    println!("Got command '~leave' by user '{}'", msg.author.name);
+    let removed = bot::ENTRY.lock().unwrap().as_mut().and_then(|entry| {
+        if let Some(teams) = entry.teams.as_mut() {
+            teams.leave(*msg.author.id.as_u64());
+        }
+        entry.players.shift_remove(msg.author.id.as_u64())
+    });
+    if removed.is_some() {
+        try_say!(
+            ctx,
+            msg,
+            format!("{} さんのエントリーを取り消しました。", msg.author.name)
+        );
+    } else {
+        try_say!(ctx, msg, "エントリーしていません。");
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Challenges the mentioned user to a duel as `~duel @user [num] [langs]`. `~duel giveup` skips the current problem and `~duel resign` concedes."]
+#[bucket = "long"]
+pub fn duel(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    println!("Got command '~duel' by user '{}'", msg.author.name);
+    if msg.author.bot {
+        return Ok(());
+    }
+    let author = *msg.author.id.as_u64();
+    match args.current() {
+        Some("giveup") => {
+            if !duel::giveup(&ctx.http, msg.channel_id, author) {
+                try_say!(ctx, msg, "このチャンネルで決闘をしていません。");
+            }
+            return Ok(());
+        }
+        Some("resign") => {
+            if !duel::resign(&ctx.http, msg.channel_id, author) {
+                try_say!(ctx, msg, "このチャンネルで決闘をしていません。");
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+    let opponent = match msg.mentions.first() {
+        Some(user) if !user.bot && user.id != msg.author.id => user,
+        _ => {
+            try_say!(
+                ctx,
+                msg,
+                "`~duel @ユーザー [問題数] [言語]` の形式で相手を指定してください。"
+            );
+            return Ok(());
+        }
+    };
+    match parser::duel(&mut args) {
+        Err(err_msg) => {
+            try_say!(ctx, msg, err_msg);
+        }
+        Ok(parser::DuelOption {
+            num,
+            languages,
+            target,
+        }) => {
+            let busy = {
+                let duels = duel::DUELS.lock().unwrap();
+                duel::is_dueling(&duels, author) || duel::is_dueling(&duels, *opponent.id.as_u64())
+            };
+            if busy {
+                try_say!(ctx, msg, "どちらかがすでに決闘中です。");
+                return Ok(());
+            }
+            duel::invite(
+                &ctx.http,
+                duel::Duel::new(
+                    *msg.channel_id.as_u64(),
+                    [
+                        duel::Player::new(author, msg.author.name.clone()),
+                        duel::Player::new(*opponent.id.as_u64(), opponent.name.clone()),
+                    ],
+                    num,
+                    target,
+                    languages,
+                ),
+            );
+        }
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Accepts the duel you are challenged to."]
+#[bucket = "basic"]
+pub fn accept(ctx: &mut Context, msg: &Message) -> CommandResult {
+    println!("Got command '~accept' by user '{}'", msg.author.name);
+    if !duel::accept(&ctx.http, msg.channel_id, *msg.author.id.as_u64(), None) {
+        try_say!(ctx, msg, "申し込まれている決闘はありません。");
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Shows the statistics of yourself or the mentioned user."]
+#[bucket = "basic"]
+pub fn stats(ctx: &mut Context, msg: &Message) -> CommandResult {
+    use crate::rating::RATINGS;
+    println!("Got command '~stats' by user '{}'", msg.author.name);
+    let user = msg.mentions.first().unwrap_or(&msg.author);
+    try_say!(
+        ctx,
+        msg,
+        format!(
+            "{} さんの成績\nレート: {}\n{}\n{}",
+            user.name,
+            RATINGS.lock().unwrap().get(*user.id.as_u64()),
+            stats::STATS
+                .lock()
+                .unwrap()
+                .get(*user.id.as_u64())
+                .as_string(),
+            daily::DAILY
+                .lock()
+                .unwrap()
+                .get(*user.id.as_u64())
+                .as_string()
+        )
+    );
+    Ok(())
+}
+
+#[command]
+#[description = "Runs a tournament of duels: `~tournament open [num] [langs] [--double]`, `join`, `leave`, `start`, `play <match>` and `cancel`."]
+#[bucket = "long"]
+pub fn tournament(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    use crate::tournament::{Entrant, Tournament, TOURNAMENT};
+    println!("Got command '~tournament' by user '{}'", msg.author.name);
+    if msg.author.bot {
+        return Ok(());
+    }
+    let author = *msg.author.id.as_u64();
+    let subcommand = args.single::<String>().unwrap_or_default();
+    if subcommand == "play" {
+        match args.single::<u32>() {
+            Err(_) => try_say!(
+                ctx,
+                msg,
+                "`~tournament play <番号>` の形式で試合を指定してください。"
+            ),
+            Ok(id) => {
+                if let Err(why) = tournament::play(&ctx.http, msg.channel_id, author, id) {
+                    try_say!(ctx, msg, why);
                 }
             }
         }
+        return Ok(());
+    }
+    let mut guard = TOURNAMENT.lock().unwrap();
+    let state = &mut *guard;
+    match (subcommand.as_str(), state.tournament.as_mut()) {
+        ("open", Some(current)) if current.champion.is_none() => {
+            try_say!(ctx, msg, "すでにトーナメントが開催されています。");
+            return Ok(());
+        }
+        ("open", _) => match parser::tournament(&mut args) {
+            Err(err_msg) => {
+                try_say!(ctx, msg, err_msg);
+                return Ok(());
+            }
+            Ok(option) => {
+                state.generation += 1;
+                state.tournament = Some(Tournament::new(
+                    author,
+                    *msg.channel_id.as_u64(),
+                    state.generation,
+                    option,
+                ));
+                try_say!(
+                    ctx,
+                    msg,
+                    "トーナメントのエントリーを開始しました。\n`~tournament join` でエントリーしてください。"
+                );
+            }
+        },
+        ("join", Some(current)) if !current.started => {
+            if current.entrants.iter().all(|entrant| entrant.id != author) {
+                current.entrants.push(Entrant {
+                    id: author,
+                    name: msg.author.name.clone(),
+                });
+            }
+            try_say!(
+                ctx,
+                msg,
+                format!(
+                    "{} さんがトーナメントにエントリーしました。 (参加者 {} 人)",
+                    msg.author.name,
+                    current.entrants.len()
+                )
+            );
+        }
+        ("leave", Some(current)) if !current.started => {
+            current.entrants.retain(|entrant| entrant.id != author);
+            try_say!(
+                ctx,
+                msg,
+                format!("{} さんのエントリーを取り消しました。", msg.author.name)
+            );
+        }
+        ("join", _) | ("leave", _) => {
+            try_say!(
+                ctx,
+                msg,
+                "現在トーナメントのエントリーを受け付けていません。"
+            );
+            return Ok(());
+        }
+        ("start", Some(current)) | ("cancel", Some(current)) if current.organizer != author => {
+            try_say!(ctx, msg, "トーナメントの主催者のみ実行できます。");
+            return Ok(());
+        }
+        ("start", Some(current)) if !current.started => {
+            if current.entrants.len() < 2 {
+                try_say!(ctx, msg, "参加者が2人以上必要です。");
+                return Ok(());
+            }
+            current.build();
+            try_say!(
+                ctx,
+                msg,
+                format!("トーナメントを開始します。\n{}", current.as_string())
+            );
+            tournament::sync(state)?;
+            // `DUELS` is locked before `TOURNAMENT`, so the matches are started after releasing it.
+            drop(guard);
+            duel::start_matches(&ctx.http);
+            return Ok(());
+        }
+        ("cancel", Some(_)) => {
+            state.tournament = None;
+            try_say!(ctx, msg, "トーナメントを中止しました。");
+        }
+        ("start", _) | ("cancel", _) => {
+            try_say!(ctx, msg, "開始前のトーナメントはありません。");
+            return Ok(());
+        }
+        _ => {
+            try_say!(
+                ctx,
+                msg,
+                "`~tournament open|join|leave|start|play|cancel` を指定してください。"
+            );
+            return Ok(());
+        }
+    }
+    Ok(tournament::sync(state)?)
+}
+
+#[command]
+#[description = "Shows the bracket of the tournament."]
+#[bucket = "basic"]
+pub fn bracket(ctx: &mut Context, msg: &Message) -> CommandResult {
+    println!("Got command '~bracket' by user '{}'", msg.author.name);
+    match &tournament::TOURNAMENT.lock().unwrap().tournament {
+        Some(current) => try_say!(ctx, msg, current.as_string()),
+        None => try_say!(ctx, msg, "トーナメントは開催されていません。"),
     }
     Ok(())
 }
@@ -304,10 +1158,11 @@ pub fn unrated(ctx: &mut Context, msg: &Message) -> CommandResult {
     println!("Got command '~unrated' by user '{}'", msg.author.name);
     loop {
         if let (Ok(mut quiz), Ok(mut result)) = (bot::QUIZ.lock(), bot::CONTEST_RESULT.lock()) {
-            if quiz.is_contesting() {
+            if quiz.is_game() {
                 try_say!(ctx, msg, "コンテストを中止します。");
                 *quiz = bot::Status::StandingBy;
                 *result = IndexMap::new();
+                *bot::ENTRY.lock().unwrap() = None;
             } else {
                 try_say!(ctx, msg, "現在コンテストは開催されていません。");
             }
@@ -318,20 +1173,195 @@ pub fn unrated(ctx: &mut Context, msg: &Message) -> CommandResult {
 }
 
 #[command]
-#[description = "Gives hint as response."]
+#[description = "Exports the problems of the last contest as a file, which `~contest --set` accepts."]
+#[bucket = "long"]
+pub fn export(ctx: &mut Context, msg: &Message) -> CommandResult {
+    use crate::problem_set::ProblemSet;
+    println!("Got command '~export' by user '{}'", msg.author.name);
+    if bot::QUIZ.lock().unwrap().is_contesting() {
+        try_say!(ctx, msg, "コンテスト中はエクスポートできません。");
+        return Ok(());
+    }
+    let library = bot::CONTEST_LIBRARY.lock().unwrap();
+    if library.problems().is_empty() {
+        try_say!(ctx, msg, "エクスポートできるコンテストがありません。");
+        return Ok(());
+    }
+    let buffer = ProblemSet::new(library.seed(), library.problems()).to_toml()?;
+    msg.channel_id.send_files(
+        &ctx,
+        vec![(buffer.as_bytes(), "contest.toml")],
+        |m| {
+            m.content(format!(
+                "前回のコンテストの問題セットです。 (seed: {})\n`~contest --set` にこのファイルを添付すると同じ問題で再戦できます。",
+                library.seed()
+            ))
+        },
+    )?;
+    Ok(())
+}
+
+#[command]
+#[description = "Posts the daily puzzle, the same for everyone today. Answer it in DM. `~daily share` posts your result without spoilers."]
+#[bucket = "basic"]
+pub fn daily(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    println!("Got command '~daily' by user '{}'", msg.author.name);
+    let author = *msg.author.id.as_u64();
+    match args.single::<String>().ok().as_ref().map(String::as_str) {
+        None => try_say!(ctx, msg, daily::problem(author, msg.is_private())),
+        Some("share") => match daily::share(author) {
+            Some(summary) => try_say!(ctx, msg, summary),
+            None => try_say!(ctx, msg, "本日のデイリーはまだ解いていません。"),
+        },
+        Some(_) => try_say!(ctx, msg, "使い方: `~daily` または `~daily share`"),
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Practices alone in DM: `~practice [lang] [num] [--level easy|normal|hard] [--target secs]`, `skip` and `stop`. Results go to `~stats` but never to ratings."]
+#[bucket = "basic"]
+pub fn practice(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    println!("Got command '~practice' by user '{}'", msg.author.name);
+    if !msg.is_private() {
+        try_say!(ctx, msg, "練習は DM で行ってください。");
+        return Ok(());
+    }
+    let in_progress = match args.current() {
+        Some("skip") => practice::skip(ctx, msg),
+        Some("stop") => practice::stop(ctx, msg),
+        _ => match parser::practice(&mut args) {
+            Ok(option) => {
+                let lang = bot::Lang::from(option.lang);
+                practice::start(ctx, msg, lang, option.num, option.difficulty, option.target);
+                true
+            }
+            Err(err_msg) => {
+                try_say!(ctx, msg, err_msg);
+                true
+            }
+        },
+    };
+    if !in_progress {
+        try_say!(ctx, msg, "練習中ではありません。");
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Shows the words due for review and the progress per language. `~review start [lang] [num]` reviews the due words in DM."]
+#[bucket = "basic"]
+pub fn review(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    println!("Got command '~review' by user '{}'", msg.author.name);
+    let author = *msg.author.id.as_u64();
+    if args.current() != Some("start") {
+        let progress = review::REVIEWS.lock().unwrap().progress(author);
+        try_say!(ctx, msg, progress);
+        return Ok(());
+    }
+    if !msg.is_private() {
+        try_say!(ctx, msg, "復習は DM で行ってください。");
+        return Ok(());
+    }
+    args.advance();
+    let lang = args.current().and_then(bot::Lang::from_code);
+    if lang.is_some() {
+        args.advance();
+    }
+    let num = args.single::<usize>().unwrap_or(REVIEW_DEFAULT_NUM);
+    let mut words = review::REVIEWS.lock().unwrap().due(author, lang);
+    words.truncate(num);
+    if words.is_empty() {
+        try_say!(ctx, msg, "今日復習する単語はありません。");
+    } else {
+        practice::start_review(ctx, msg, words);
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Registers a problem set for `~contest --set` from an attached file or a pasted list, in DM."]
+#[bucket = "long"]
+pub fn problemset(ctx: &mut Context, msg: &Message, args: Args) -> CommandResult {
+    use crate::problem_set::{ProblemSet, PROBLEM_SETS};
+    println!("Got command '~problemset' by user '{}'", msg.author.name);
+    if !msg.is_private() {
+        try_say!(ctx, msg, "問題セットは DM で登録してください。");
+        return Ok(());
+    }
+    let parsed = match msg.attachments.first() {
+        Some(attachment) => attachment
+            .download()
+            .map_err(BotError::from)
+            .and_then(|buffer| ProblemSet::parse(&buffer)),
+        None if args.rest().trim().is_empty() => {
+            match PROBLEM_SETS.lock().unwrap().get(msg.author.id.as_u64()) {
+                Some(problem_set) => try_say!(
+                    ctx,
+                    msg,
+                    format!(
+                        "{}問の問題セットが登録されています。",
+                        problem_set.problems.len()
+                    )
+                ),
+                None => try_say!(ctx, msg, "問題セットは登録されていません。"),
+            }
+            return Ok(());
+        }
+        None => ProblemSet::parse(args.rest().as_bytes()),
+    };
+    match parsed {
+        Ok(problem_set) => {
+            try_say!(
+                ctx,
+                msg,
+                format!(
+                    "{}問の問題セットを登録しました。\nチャンネルで `~contest --set` を実行すると開始します。",
+                    problem_set.problems.len()
+                )
+            );
+            PROBLEM_SETS
+                .lock()
+                .unwrap()
+                .insert(*msg.author.id.as_u64(), problem_set);
+        }
+        Err(why) => {
+            try_say!(
+                ctx,
+                msg,
+                format!("問題セットを読み込めませんでした: {}", why)
+            );
+        }
+    }
+    Ok(())
+}
+
+#[command]
+#[description = "Gives hint as response. `~hint --meaning` shows the meaning of the answer instead."]
 #[bucket = "long"]
 pub fn hint(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
     println!("Got command '~hint' by user '{}'", msg.author.name);
     if_chain! {
         if !msg.author.bot;
         if let Ok(mut guard) = bot::QUIZ.lock();
-        if !guard.is_standing_by();
+        if guard.is_holding() || guard.is_contesting() || guard.is_chaining();
         then {
-            let mut g = UnicodeSegmentation::graphemes(guard.ans().unwrap().as_str(), true).collect::<Vec<&str>>();
+            let mut g = guard.hints().unwrap();
             match parser::hint(&mut args) {
                 Err(err_msg) => {
                     try_say!(ctx,msg,format!("{}", err_msg));
                 },
+                Ok(parser::Hint::Meaning) => {
+                    let meaning = guard
+                        .get_dictionary()
+                        .ok()
+                        .and_then(|dic| dic.entry(guard.ans().unwrap()))
+                        .and_then(|entry| entry.meaning.clone());
+                    match meaning {
+                        Some(meaning) => try_say!(ctx,msg,format!("答えの意味は... => {}", meaning)),
+                        None => try_say!(ctx,msg,"この単語には意味が登録されていません。"),
+                    }
+                },
                 Ok(parser::Hint::First(num)) | Ok(parser::Hint::Random(num)) if num == 0 => {
                     try_say!(ctx,msg,"ゼロ文字ヒントはだせません。");
                 },
@@ -356,20 +1386,21 @@ pub fn hint(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
                         .expect("fail to post");
                 },
                 Ok(parser::Hint::Random(num)) => {
-                    let star = "*";
-                    let mut hit_str: Vec<&str> = std::iter::repeat(star).take(g.len()).collect();
-                    for idx in rand::seq::index::sample(&mut rand::thread_rng(), g.len(), num).into_iter() {
-                        if let Some(elem) = hit_str.get_mut(idx) {
-                            *elem = g.get(idx).unwrap();
-                        }
-                    }
+                    let hint = match guard.get_contest_num() {
+                        Some((count, _)) => executors::random_hint(
+                            &g,
+                            num,
+                            &mut bot::CONTEST_LIBRARY.lock().unwrap().hint_rng(*count),
+                        ),
+                        None => executors::random_hint(&g, num, &mut rand::thread_rng()),
+                    };
                     msg.channel_id
                         .say(
                             &ctx,
                             format!(
                                 "ランダムヒント {len} 文字... => `{hint}` ",
                                 len = num,
-                                hint = hit_str.join(""),
+                                hint = hint,
                             ),
                         )
                         .expect("fail to post");
@@ -402,21 +1433,10 @@ fn nazonazo_help(
 }
 
 fn sync_setting() -> Result<(), BotError> {
-    use quick_error::ResultExt;
-    let path = std::path::Path::new("/tmp/settings/settings.toml");
-    let mut conf = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(path)
-        .context(path)?;
-    conf.write_all(
-        toml::to_string(&*settings::SETTINGS.lock().unwrap())
-            .context("/tmp/settings/settings.toml")?
-            .as_bytes(),
+    settings::sync_config(
+        &*settings::SETTINGS.lock().unwrap(),
+        "/tmp/settings/settings.toml",
     )
-    .context(path)?;
-    conf.sync_all().context(path)?;
-    Ok(())
 }
 
 #[command]
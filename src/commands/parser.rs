@@ -1,5 +1,11 @@
 use super::facade;
-use clap::{App, AppSettings, Arg};
+use crate::{bot, history, practice, puzzle, schedule};
+use clap::{App, AppSettings, Arg, ArgMatches, ErrorKind};
+use itertools::Itertools;
+
+lazy_static! {
+    static ref DEFAULT_LANGUAGES: String = facade::QUIZ_COMMANDS.to_vec().join(",");
+}
 
 fn range_validator(low: u32, up: u32) -> Box<dyn Fn(String) -> Result<(), String>> {
     Box::new(move |num: String| match num.parse::<u32>() {
@@ -12,6 +18,14 @@ fn range_validator(low: u32, up: u32) -> Box<dyn Fn(String) -> Result<(), String
     })
 }
 
+/// `--teams` needs at least two teams to split players into.
+fn teams_validator(num: String) -> Result<(), String> {
+    match num.parse::<u32>() {
+        Ok(num) if (2..=10).contains(&num) => Ok(()),
+        _ => Err(format!("`{}` is invalid. specify 2 to 10 teams.", num)),
+    }
+}
+
 fn parse_validator<T: std::str::FromStr>(num: String) -> Result<(), String> {
     num.parse::<T>()
         .map(|_| ())
@@ -19,47 +33,705 @@ fn parse_validator<T: std::str::FromStr>(num: String) -> Result<(), String> {
 }
 
 fn language_validator(language: String) -> Result<(), String> {
-    if !facade::QUIZ_COMMANDS_REGEX.is_match(&language) {
-        Err(format!("unexpected language '{}'.", language))
-    } else {
-        Ok(())
+    let mut iter = language.splitn(2, ':');
+    let lang = iter.next().unwrap();
+    if lang == "contest" || !facade::QUIZ_COMMANDS_REGEX.is_match(lang) {
+        return Err(format!("unexpected language '{}'.", lang));
+    }
+    match iter.next().map(str::parse::<u32>) {
+        None => Ok(()),
+        Some(Ok(0)) => Err(format!("the weight of '{}' must be positive.", lang)),
+        Some(Ok(_)) => Ok(()),
+        Some(Err(_)) => Err(format!("invalid weight of '{}'.", language)),
+    }
+}
+
+pub(crate) fn parse_language(language: &str) -> (String, u32) {
+    let mut iter = language.splitn(2, ':');
+    let lang = iter.next().unwrap().to_string();
+    let weight = iter
+        .next()
+        .map_or(1, |weight| weight.parse::<u32>().unwrap());
+    (lang, weight)
+}
+
+#[derive(Debug)]
+pub struct ContestOption {
+    pub num: Option<u32>,
+    pub languages: Vec<(String, u32)>,
+    pub quota: bool,
+    pub fresh: Option<u32>,
+    pub seed: Option<u64>,
+    pub problem_set: bool,
+    pub shuffle: bool,
+    pub entry: Option<u32>,
+    pub late: u32,
+    /// The channel default is used unless given.
+    pub puzzle: Option<puzzle::Kind>,
+    /// Only the words with this tag are asked.
+    pub tag: Option<String>,
+}
+
+fn contest_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("number")
+            .required_unless("set")
+            .validator(range_validator(1, 100))
+            .help("Number of contest problems"),
+        Arg::with_name("languages")
+            .required(true)
+            .use_delimiter(true)
+            .validator(language_validator)
+            .takes_value(true)
+            .default_value(DEFAULT_LANGUAGES.as_str())
+            .min_values(1)
+            .help("List of contest languages, optionally weighted as `en:3,ja:1`"),
+        Arg::with_name("quota")
+            .short("q")
+            .long("quota")
+            .takes_value(false)
+            .help("Treats the weights as the exact number of problems per language")
+            .required(false),
+        Arg::with_name("fresh")
+            .long("fresh")
+            .takes_value(true)
+            .validator(range_validator(0, history::RETENTION_DAYS as u32))
+            .help("Avoids words asked in this channel within the last N days")
+            .required(false),
+        Arg::with_name("seed")
+            .long("seed")
+            .takes_value(true)
+            .validator(parse_validator::<u64>)
+            .help("Seed of problem selection, which makes the contest reproducible")
+            .required(false),
+        Arg::with_name("set")
+            .long("set")
+            .alias("replay")
+            .takes_value(false)
+            .help("Runs the attached problem set, or the one registered by `~problemset` in DM")
+            .required(false),
+        Arg::with_name("shuffle")
+            .long("shuffle")
+            .takes_value(false)
+            .requires("set")
+            .help("Shuffles the order of the problem set")
+            .required(false),
+        Arg::with_name("entry")
+            .long("entry")
+            .takes_value(true)
+            .validator(range_validator(0, 600))
+            .help("Opens registration by `~join` for N seconds, and only registered players can answer")
+            .required(false),
+        Arg::with_name("late")
+            .long("late")
+            .takes_value(true)
+            .validator(parse_validator::<u32>)
+            .requires("entry")
+            .help("Accepts late `~join` until the N-th problem is asked")
+            .required(false),
+        Arg::with_name("puzzle")
+            .long("puzzle")
+            .takes_value(true)
+            .possible_values(&puzzle::Kind::NAMES)
+            .help("How problems are made from the answers")
+            .required(false),
+        Arg::with_name("tag")
+            .long("tag")
+            .takes_value(true)
+            .help("Asks only the words with the tag, listed by `~tags <lang>`")
+            .required(false),
+    ]
+}
+
+/// Fails unless every language of `langs` has a word with `tag`.
+fn check_tag<'a, I: Iterator<Item = &'a str>>(langs: I, tag: Option<&str>) -> clap::Result<()> {
+    let tag = match tag {
+        Some(tag) => tag,
+        None => return Ok(()),
+    };
+    for lang in langs {
+        if !bot::select_dictionary_from_str(lang).has_tag(tag) {
+            return Err(clap::Error::with_description(
+                &format!("no word of '{}' has the tag '{}'.", lang, tag),
+                ErrorKind::ValueValidation,
+            ));
+        }
     }
+    Ok(())
+}
+
+fn contest_option(matches: &ArgMatches) -> clap::Result<ContestOption> {
+    let num = matches
+        .value_of("number")
+        .map(|num| num.parse::<u32>().unwrap());
+    let languages = matches
+        .values_of("languages")
+        .unwrap()
+        .map(parse_language)
+        .collect::<Vec<_>>();
+    let quota = matches.is_present("quota");
+    let fresh = matches
+        .value_of("fresh")
+        .map(|days| days.parse::<u32>().unwrap());
+    let seed = matches
+        .value_of("seed")
+        .map(|seed| seed.parse::<u64>().unwrap());
+    let problem_set = matches.is_present("set");
+    let shuffle = matches.is_present("shuffle");
+    let entry = matches
+        .value_of("entry")
+        .map(|secs| secs.parse::<u32>().unwrap());
+    let late = matches
+        .value_of("late")
+        .map_or(0, |num| num.parse::<u32>().unwrap());
+    let tag = matches.value_of("tag").map(str::to_lowercase);
+    if !problem_set {
+        check_tag(
+            languages.iter().map(|(lang, _)| lang.as_str()),
+            tag.as_deref(),
+        )?;
+    }
+    if let Some(num) = num.filter(|_| quota) {
+        if languages.iter().map(|(_, weight)| weight).sum::<u32>() != num {
+            return Err(clap::Error::with_description(
+                &format!("the sum of quotas must be equal to {}.", num),
+                ErrorKind::ValueValidation,
+            ));
+        }
+    }
+    Ok(ContestOption {
+        num,
+        languages,
+        quota,
+        fresh,
+        seed,
+        problem_set,
+        shuffle,
+        entry,
+        late,
+        puzzle: matches.value_of("puzzle").and_then(puzzle::Kind::from_name),
+        tag,
+    })
 }
 
 pub(crate) fn contest(
     args: &mut serenity::framework::standard::Args,
-) -> clap::Result<(u32, Vec<String>)> {
+) -> clap::Result<ContestOption> {
     App::new("contest")
         .version("0.0.1")
         .setting(AppSettings::ColorNever)
+        .args(&contest_args())
+        .get_matches_from_safe(
+            std::iter::once("contest".to_string())
+                .chain(args.iter::<String>().filter_map(Result::ok)),
+        )
+        .and_then(|matches| contest_option(&matches))
+}
+
+#[derive(Debug)]
+pub enum TeamBy {
+    Join,
+    Role,
+    Auto,
+}
+
+#[derive(Debug)]
+pub struct TeamOption {
+    pub by: TeamBy,
+    pub teams: u32,
+}
+
+/// Seconds of registration when `~contest team` is run without `--entry`.
+const DEFAULT_TEAM_ENTRY: u32 = 60;
+
+/// Parses `~contest team`. Role mentions are skipped, since they are read from the message.
+pub(crate) fn team_contest(
+    args: &mut serenity::framework::standard::Args,
+) -> clap::Result<(ContestOption, TeamOption)> {
+    App::new("team")
+        .version("0.0.1")
+        .setting(AppSettings::ColorNever)
+        .args(&contest_args())
         .arg(
-            Arg::with_name("number")
+            Arg::with_name("by")
+                .long("by")
+                .takes_value(true)
+                .possible_values(&["join", "role", "auto"])
+                .default_value("join")
+                .help("Splits players by `~join <team>`, by the mentioned roles, or by rating")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("teams")
+                .long("teams")
+                .takes_value(true)
+                .validator(teams_validator)
+                .default_value("2")
+                .help("Number of teams balanced by `--by auto`")
+                .required(false),
+        )
+        .get_matches_from_safe(
+            std::iter::once("team".to_string()).chain(
+                args.iter::<String>()
+                    .filter_map(Result::ok)
+                    .filter(|arg| !arg.starts_with("<@&")),
+            ),
+        )
+        .and_then(|matches| {
+            let mut contest = contest_option(&matches)?;
+            contest.entry = contest.entry.or(Some(DEFAULT_TEAM_ENTRY));
+            let by = match matches.value_of("by").unwrap() {
+                "role" => TeamBy::Role,
+                "auto" => TeamBy::Auto,
+                _ => TeamBy::Join,
+            };
+            let teams = matches.value_of("teams").unwrap().parse::<u32>().unwrap();
+            Ok((contest, TeamOption { by, teams }))
+        })
+}
+
+/// Management subcommands of `~contest`, which are allowed even while a quiz is held.
+pub(crate) fn contest_subcommand(content: &str) -> Option<&str> {
+    content
+        .split_whitespace()
+        .nth(1)
+        .filter(|sub| ["schedule", "list", "cancel"].contains(sub))
+}
+
+fn datetime_validator(datetime: String) -> Result<(), String> {
+    schedule::parse_datetime(&datetime)
+        .map(|_| ())
+        .ok_or_else(|| {
+            format!(
+                "`{}` is invalid. use `YYYY-MM-DDTHH:MM` (JST) or `YYYY-MM-DDTHH:MM+09:00`.",
+                datetime
+            )
+        })
+}
+
+#[derive(Debug)]
+pub struct ScheduleOption {
+    pub at: i64,
+    pub contest: ContestOption,
+    pub remind: Vec<u32>,
+}
+
+pub(crate) fn schedule(
+    args: &mut serenity::framework::standard::Args,
+) -> clap::Result<ScheduleOption> {
+    App::new("schedule")
+        .version("0.0.1")
+        .setting(AppSettings::ColorNever)
+        .arg(
+            Arg::with_name("datetime")
                 .required(true)
-                .validator(range_validator(1, 100))
-                .help("Number of contest problems"),
+                .validator(datetime_validator)
+                .help("Start time of the contest"),
         )
+        .args(&contest_args())
+        .arg(
+            Arg::with_name("remind")
+                .long("remind")
+                .use_delimiter(true)
+                .takes_value(true)
+                .validator(parse_validator::<u32>)
+                .default_value("30,5")
+                .help("Minutes before the start to post reminders")
+                .required(false),
+        )
+        .get_matches_from_safe(
+            std::iter::once("schedule".to_string())
+                .chain(args.iter::<String>().filter_map(Result::ok)),
+        )
+        .and_then(|matches| {
+            let contest = contest_option(&matches)?;
+            if contest.problem_set {
+                return Err(clap::Error::with_description(
+                    "problem sets cannot be scheduled.",
+                    ErrorKind::ArgumentConflict,
+                ));
+            }
+            if contest.entry.is_some() {
+                return Err(clap::Error::with_description(
+                    "registration by `--entry` cannot be scheduled.",
+                    ErrorKind::ArgumentConflict,
+                ));
+            }
+            let at = schedule::parse_datetime(matches.value_of("datetime").unwrap()).unwrap();
+            if at <= schedule::now() {
+                return Err(clap::Error::with_description(
+                    "the datetime is already past.",
+                    ErrorKind::ValueValidation,
+                ));
+            }
+            let remind = matches
+                .values_of("remind")
+                .unwrap()
+                .map(|minutes| minutes.parse::<u32>().unwrap())
+                .collect();
+            Ok(ScheduleOption {
+                at,
+                contest,
+                remind,
+            })
+        })
+}
+
+#[derive(Debug)]
+pub struct PracticeOption {
+    pub lang: String,
+    pub num: u32,
+    pub difficulty: practice::Difficulty,
+    pub target: Option<u32>,
+}
+
+pub(crate) fn practice(
+    args: &mut serenity::framework::standard::Args,
+) -> clap::Result<PracticeOption> {
+    App::new("practice")
+        .version("0.0.1")
+        .setting(AppSettings::ColorNever)
+        .arg(
+            Arg::with_name("language")
+                .validator(language_validator)
+                .default_value("en")
+                .help("Language of the problems"),
+        )
+        .arg(
+            Arg::with_name("number")
+                .validator(range_validator(0, 100))
+                .default_value("10")
+                .help("Number of problems"),
+        )
+        .arg(
+            Arg::with_name("level")
+                .long("level")
+                .takes_value(true)
+                .possible_values(&practice::Difficulty::NAMES)
+                .default_value("normal")
+                .help("Length of the answers")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("target")
+                .long("target")
+                .takes_value(true)
+                .validator(range_validator(0, 600))
+                .help("Seconds aimed at for each problem")
+                .required(false),
+        )
+        .get_matches_from_safe(
+            std::iter::once("practice".to_string())
+                .chain(args.iter::<String>().filter_map(Result::ok)),
+        )
+        .map(|matches| PracticeOption {
+            lang: parse_language(matches.value_of("language").unwrap()).0,
+            num: matches.value_of("number").unwrap().parse::<u32>().unwrap(),
+            difficulty: practice::Difficulty::from_name(matches.value_of("level").unwrap())
+                .unwrap(),
+            target: matches
+                .value_of("target")
+                .map(|secs| secs.parse::<u32>().unwrap()),
+        })
+}
+
+#[derive(Debug)]
+pub struct SurvivalOption {
+    pub languages: Vec<String>,
+    pub limit: u32,
+    pub entry: u32,
+}
+
+pub(crate) fn survival(
+    args: &mut serenity::framework::standard::Args,
+) -> clap::Result<SurvivalOption> {
+    App::new("survival")
+        .version("0.0.1")
+        .setting(AppSettings::ColorNever)
         .arg(
             Arg::with_name("languages")
-                .required(true)
                 .use_delimiter(true)
                 .validator(language_validator)
                 .takes_value(true)
-                .default_value(facade::QUIZ_COMMANDS.to_vec().join(",").as_str())
+                .default_value("en")
                 .min_values(1)
-                .help("List of contest languages"),
+                .help("List of languages"),
+        )
+        .arg(
+            Arg::with_name("limit")
+                .long("limit")
+                .takes_value(true)
+                .validator(range_validator(0, 600))
+                .default_value("60")
+                .help("Time limit in seconds of the first round, which gets shorter every round")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("entry")
+                .long("entry")
+                .takes_value(true)
+                .validator(range_validator(0, 600))
+                .default_value("30")
+                .help("Seconds of registration by `~join`")
+                .required(false),
         )
         .get_matches_from_safe(
-            std::iter::once("contest".to_string())
+            std::iter::once("survival".to_string())
                 .chain(args.iter::<String>().filter_map(Result::ok)),
         )
-        .map(|matches| {
+        .map(|matches| SurvivalOption {
+            languages: matches
+                .values_of("languages")
+                .unwrap()
+                .map(|language| parse_language(language).0)
+                .unique()
+                .collect(),
+            limit: matches.value_of("limit").unwrap().parse::<u32>().unwrap(),
+            entry: matches.value_of("entry").unwrap().parse::<u32>().unwrap(),
+        })
+}
+
+#[derive(Debug)]
+pub struct CoopOption {
+    pub languages: Vec<(String, u32)>,
+    pub minutes: u32,
+    pub target: Option<u32>,
+    pub open: u32,
+}
+
+pub(crate) fn coop(args: &mut serenity::framework::standard::Args) -> clap::Result<CoopOption> {
+    App::new("coop")
+        .version("0.0.1")
+        .setting(AppSettings::ColorNever)
+        .arg(
+            Arg::with_name("languages")
+                .use_delimiter(true)
+                .validator(language_validator)
+                .takes_value(true)
+                .default_value(DEFAULT_LANGUAGES.as_str())
+                .min_values(1)
+                .help("List of languages, optionally weighted as `en:3,ja:1`"),
+        )
+        .arg(
+            Arg::with_name("minutes")
+                .short("m")
+                .long("minutes")
+                .takes_value(true)
+                .validator(range_validator(0, 60))
+                .default_value("5")
+                .help("Time limit in minutes")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("target")
+                .long("target")
+                .takes_value(true)
+                .validator(range_validator(0, 1000))
+                .help("Ends the challenge as soon as this number of puzzles are solved")
+                .required(false),
+        )
+        .arg(
+            Arg::with_name("open")
+                .long("open")
+                .takes_value(true)
+                .validator(range_validator(0, 10))
+                .default_value("3")
+                .help("Number of puzzles open at once")
+                .required(false),
+        )
+        .get_matches_from_safe(
+            std::iter::once("coop".to_string()).chain(args.iter::<String>().filter_map(Result::ok)),
+        )
+        .and_then(|matches| {
+            let languages = matches
+                .values_of("languages")
+                .unwrap()
+                .map(parse_language)
+                .collect::<Vec<_>>();
+            Ok(CoopOption {
+                languages,
+                minutes: matches.value_of("minutes").unwrap().parse::<u32>().unwrap(),
+                target: matches
+                    .value_of("target")
+                    .map(|target| target.parse::<u32>().unwrap()),
+                open: matches.value_of("open").unwrap().parse::<u32>().unwrap(),
+            })
+        })
+}
+
+#[derive(Debug)]
+pub struct DuelOption {
+    pub num: u32,
+    pub languages: Vec<(String, u32)>,
+    pub target: u32,
+}
+
+/// Parses `~duel`. The opponent is read from the mentions of the message.
+pub(crate) fn duel(args: &mut serenity::framework::standard::Args) -> clap::Result<DuelOption> {
+    App::new("duel")
+        .version("0.0.1")
+        .setting(AppSettings::ColorNever)
+        .arg(
+            Arg::with_name("number")
+                .validator(range_validator(1, 100))
+                .default_value("10")
+                .help("Number of duel problems"),
+        )
+        .arg(
+            Arg::with_name("languages")
+                .use_delimiter(true)
+                .validator(language_validator)
+                .takes_value(true)
+                .default_value(DEFAULT_LANGUAGES.as_str())
+                .min_values(1)
+                .help("List of duel languages, optionally weighted as `en:3,ja:1`"),
+        )
+        .arg(
+            Arg::with_name("target")
+                .long("target")
+                .takes_value(true)
+                .validator(range_validator(0, 100))
+                .help("Score which wins the duel, more than half of the problems by default")
+                .required(false),
+        )
+        .get_matches_from_safe(
+            std::iter::once("duel".to_string()).chain(
+                args.iter::<String>()
+                    .filter_map(Result::ok)
+                    .filter(|arg| !arg.starts_with("<@")),
+            ),
+        )
+        .and_then(|matches| {
             let num = matches.value_of("number").unwrap().parse::<u32>().unwrap();
             let languages = matches
                 .values_of("languages")
                 .unwrap()
-                .map(str::to_string)
+                .map(parse_language)
                 .collect::<Vec<_>>();
-            (num, languages)
+            let target = matches
+                .value_of("target")
+                .map_or(num / 2 + 1, |target| target.parse::<u32>().unwrap());
+            if target > num {
+                return Err(clap::Error::with_description(
+                    "the target score exceeds the number of problems.",
+                    ErrorKind::ValueValidation,
+                ));
+            }
+            Ok(DuelOption {
+                num,
+                languages,
+                target,
+            })
+        })
+}
+
+#[derive(Debug)]
+pub struct TournamentOption {
+    pub num: u32,
+    pub languages: Vec<(String, u32)>,
+    pub double: bool,
+}
+
+pub(crate) fn tournament(
+    args: &mut serenity::framework::standard::Args,
+) -> clap::Result<TournamentOption> {
+    App::new("tournament")
+        .version("0.0.1")
+        .setting(AppSettings::ColorNever)
+        .arg(
+            Arg::with_name("number")
+                .validator(range_validator(1, 100))
+                .default_value("5")
+                .help("Number of problems of each match"),
+        )
+        .arg(
+            Arg::with_name("languages")
+                .use_delimiter(true)
+                .validator(language_validator)
+                .takes_value(true)
+                .default_value(DEFAULT_LANGUAGES.as_str())
+                .min_values(1)
+                .help("List of languages, optionally weighted as `en:3,ja:1`"),
+        )
+        .arg(
+            Arg::with_name("double")
+                .long("double")
+                .takes_value(false)
+                .help("Holds a double-elimination bracket")
+                .required(false),
+        )
+        .get_matches_from_safe(
+            std::iter::once("tournament".to_string())
+                .chain(args.iter::<String>().filter_map(Result::ok)),
+        )
+        .and_then(|matches| {
+            let languages = matches
+                .values_of("languages")
+                .unwrap()
+                .map(parse_language)
+                .collect::<Vec<_>>();
+            Ok(TournamentOption {
+                num: matches.value_of("number").unwrap().parse::<u32>().unwrap(),
+                languages,
+                double: matches.is_present("double"),
+            })
+        })
+}
+
+/// Parses the puzzle of a single quiz, such as `~en decoy`.
+pub(crate) fn puzzle(
+    args: &mut serenity::framework::standard::Args,
+) -> clap::Result<Option<puzzle::Kind>> {
+    App::new("quiz")
+        .version("0.0.1")
+        .setting(AppSettings::ColorNever)
+        .arg(
+            Arg::with_name("puzzle")
+                .possible_values(&puzzle::Kind::NAMES)
+                .help("How the problem is made from the answer"),
+        )
+        .get_matches_from_safe(
+            std::iter::once("quiz".to_string()).chain(args.iter::<String>().filter_map(Result::ok)),
+        )
+        .map(|matches| matches.value_of("puzzle").and_then(puzzle::Kind::from_name))
+}
+
+#[derive(Debug)]
+pub struct QuizOption {
+    pub puzzle: Option<puzzle::Kind>,
+    pub tag: Option<String>,
+}
+
+/// Parses `~en [puzzle] [--tag <tag>]` and the like for `lang`.
+pub(crate) fn quiz(
+    args: &mut serenity::framework::standard::Args,
+    lang: bot::Lang,
+) -> clap::Result<QuizOption> {
+    App::new("quiz")
+        .version("0.0.1")
+        .setting(AppSettings::ColorNever)
+        .arg(
+            Arg::with_name("puzzle")
+                .possible_values(&puzzle::Kind::NAMES)
+                .help("How the problem is made from the answer"),
+        )
+        .arg(
+            Arg::with_name("tag")
+                .long("tag")
+                .takes_value(true)
+                .help("Asks only the words with the tag, listed by `~tags <lang>`")
+                .required(false),
+        )
+        .get_matches_from_safe(
+            std::iter::once("quiz".to_string()).chain(args.iter::<String>().filter_map(Result::ok)),
+        )
+        .and_then(|matches| {
+            let tag = matches.value_of("tag").map(str::to_lowercase);
+            check_tag(std::iter::once(lang.as_code()), tag.as_deref())?;
+            Ok(QuizOption {
+                puzzle: matches.value_of("puzzle").and_then(puzzle::Kind::from_name),
+                tag,
+            })
         })
 }
 
@@ -67,6 +739,8 @@ pub(crate) fn contest(
 pub enum Hint {
     First(usize),
     Random(usize),
+    /// The meaning in the dictionary entry of the answer.
+    Meaning,
 }
 
 pub(crate) fn hint(args: &mut serenity::framework::standard::Args) -> clap::Result<Hint> {
@@ -75,7 +749,7 @@ pub(crate) fn hint(args: &mut serenity::framework::standard::Args) -> clap::Resu
         .setting(AppSettings::ColorNever)
         .arg(
             Arg::with_name("number")
-                .required(true)
+                .required_unless("meaning")
                 .validator(parse_validator::<usize>)
                 .help("Number of hint characters"),
         )
@@ -87,10 +761,22 @@ pub(crate) fn hint(args: &mut serenity::framework::standard::Args) -> clap::Resu
                 .help("Flag for random select hint")
                 .required(false),
         )
+        .arg(
+            Arg::with_name("meaning")
+                .short("m")
+                .long("meaning")
+                .takes_value(false)
+                .conflicts_with_all(&["number", "random"])
+                .help("Shows the meaning of the answer instead of characters")
+                .required(false),
+        )
         .get_matches_from_safe(
             std::iter::once("hint".to_string()).chain(args.iter::<String>().filter_map(Result::ok)),
         )
         .map(|matches| {
+            if matches.is_present("meaning") {
+                return Hint::Meaning;
+            }
             let num = matches
                 .value_of("number")
                 .unwrap()
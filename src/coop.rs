@@ -0,0 +1,194 @@
+use super::bot::{self, CheckResult, ContestData, DictionarySelector, Status};
+use super::commands::parser::CoopOption;
+use super::history;
+use super::puzzle;
+use super::sort::Sorted;
+use crate::try_say;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use serenity::client::Context;
+use serenity::http::Http;
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// An open puzzle, which is held as a normal quiz of its own.
+#[derive(Debug)]
+pub struct Puzzle {
+    pub number: u32,
+    pub quiz: Status,
+}
+
+impl Puzzle {
+    fn as_string(&self) -> String {
+        let (ans, lang) = match &self.quiz {
+            Status::Holding(ans, lang, ..) => (ans, lang),
+            _ => unreachable!(),
+        };
+        format!(
+            "#{number} ソートなぞなぞ ソート前の {symbol} な〜んだ？\n`{prob}`",
+            number = self.number,
+            symbol = lang.as_symbol(),
+            prob = ans.sorted(),
+        )
+    }
+}
+
+/// The channel solves as many puzzles as possible, or a target number of them, within the time limit.
+#[derive(Debug)]
+pub struct Coop {
+    pub puzzles: Vec<Puzzle>,
+    pub asked: u32,
+    pub solved: u32,
+    pub target: Option<u32>,
+    pub started: Instant,
+    pub limit: Duration,
+    pub contributions: IndexMap<String, ContestData>,
+    library: DictionarySelector,
+}
+
+fn say(http: &Http, channel: ChannelId, response: String) {
+    if let Err(why) = channel.say(http, response) {
+        println!("{}", why);
+    }
+}
+
+impl Coop {
+    /// Opens a new puzzle and returns its problem statement.
+    fn ask(&mut self, channel: ChannelId) -> String {
+        let (ans, lang) = self.library.next();
+        if let Err(why) = history::record(*channel.as_u64(), lang, &ans) {
+            println!("{}", why);
+        }
+        self.asked += 1;
+        let puzzle = Puzzle {
+            number: self.asked,
+            quiz: Status::Holding(ans, lang, Instant::now(), Box::new(puzzle::Sorted)),
+        };
+        let statement = puzzle.as_string();
+        self.puzzles.push(puzzle);
+        statement
+    }
+
+    fn report(&self) -> String {
+        let result = match self.target {
+            Some(target) if self.solved >= target => {
+                format!("目標の {} 問を達成しました！", target)
+            }
+            Some(target) => format!("目標の {} 問には届きませんでした...", target),
+            None => String::new(),
+        };
+        let unsolved = self
+            .puzzles
+            .iter()
+            .map(|puzzle| format!("#{}: {}", puzzle.number, puzzle.quiz.ans().unwrap()))
+            .join(", ");
+        format!(
+            "協力モードが終了しました。\nみんなで {} 問正解しました。{}\n残っていた問題の正解: {}\n貢献\n{}",
+            self.solved,
+            result,
+            unsolved,
+            bot::aggregates(&self.contributions, &[])
+        )
+    }
+}
+
+/// Starts the co-op mode and opens the first puzzles.
+pub(crate) fn start(http: &Arc<Http>, channel: ChannelId, quiz: &mut Status, option: CoopOption) {
+    let mut library = DictionarySelector::new();
+    library.set(option.languages, false, rand::random(), None);
+    let mut coop = Coop {
+        puzzles: Vec::new(),
+        asked: 0,
+        solved: 0,
+        target: option.target,
+        started: Instant::now(),
+        limit: Duration::from_secs(u64::from(option.minutes) * 60),
+        contributions: IndexMap::new(),
+        library,
+    };
+    let statements = (0..option.open).map(|_| coop.ask(channel)).join("\n");
+    say(
+        http,
+        channel,
+        format!(
+            "協力モードを始めます。{minutes} 分間でみんなで{target}問題を解きましょう！\nどの問題の答えでも受け付けます。\n{statements}",
+            minutes = option.minutes,
+            target = option
+                .target
+                .map_or(String::new(), |target| format!(" {} 問の", target)),
+            statements = statements,
+        ),
+    );
+    let (started, limit) = (coop.started, coop.limit);
+    *quiz = Status::Cooperating(coop);
+    let http = http.clone();
+    thread::spawn(move || {
+        thread::sleep(limit);
+        let quiz = &mut *bot::QUIZ.lock().unwrap();
+        match quiz {
+            Status::Cooperating(coop) if coop.started == started => {
+                say(&http, channel, format!("時間切れです！\n{}", coop.report()));
+                *quiz = Status::StandingBy;
+            }
+            _ => {}
+        }
+    });
+}
+
+/// Checks the message against every open puzzle, and opens another one for a solved puzzle.
+pub(crate) fn answer_check(ctx: &mut Context, msg: &Message, quiz: &mut Status) {
+    let coop = match quiz {
+        Status::Cooperating(coop) => coop,
+        _ => return,
+    };
+    let position =
+        coop.puzzles
+            .iter()
+            .position(|puzzle| match puzzle.quiz.answer_check(&msg.content) {
+                CheckResult::WA => false,
+                _ => true,
+            });
+    let puzzle = match position {
+        Some(position) => coop.puzzles.remove(position),
+        None => return,
+    };
+    let elapsed = puzzle.quiz.elapsed().unwrap();
+    *coop
+        .contributions
+        .entry(msg.author.name.clone())
+        .or_insert(ContestData::default()) += elapsed;
+    coop.solved += 1;
+    let solved = format!(
+        "{} さん、正解です！\n#{} の正解は\"{}\"でした！ [{:.3} sec]",
+        &msg.author.name,
+        puzzle.number,
+        puzzle.quiz.ans().unwrap(),
+        elapsed,
+    );
+    if coop.target.map_or(false, |target| coop.solved >= target) {
+        try_say!(ctx, msg, format!("{}\n{}", solved, coop.report()));
+        *quiz = Status::StandingBy;
+    } else {
+        let statement = coop.ask(msg.channel_id);
+        try_say!(ctx, msg, format!("{}\n{}", solved, statement));
+    }
+}
+
+/// Reveals the oldest open puzzle and replaces it with a new one.
+pub(crate) fn giveup(ctx: &mut Context, msg: &Message, coop: &mut Coop) {
+    let puzzle = coop.puzzles.remove(0);
+    let statement = coop.ask(msg.channel_id);
+    try_say!(
+        ctx,
+        msg,
+        format!(
+            "#{} の正解は \"{}\" でした...\n{}",
+            puzzle.number,
+            puzzle.quiz.ans().unwrap(),
+            statement
+        )
+    );
+}
@@ -0,0 +1,219 @@
+use super::bot::{self, Lang};
+use super::schedule;
+use super::settings;
+use super::sort::Sorted;
+use crate::try_say;
+use chrono::NaiveDate;
+use rand::{rngs::StdRng, SeedableRng};
+use serde_derive::{Deserialize, Serialize};
+use serenity::client::Context;
+use serenity::model::channel::Message;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use unicode_segmentation::UnicodeSegmentation;
+
+const DAILY_PATH: &str = "/tmp/settings/daily.toml";
+
+/// The result of a user on the daily puzzle. Days are those of `schedule::today`.
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub(crate) struct Record {
+    /// The day of `misses`.
+    day: i64,
+    /// Wrong guesses on `day`.
+    misses: u32,
+    last_solved: Option<i64>,
+    streak: u32,
+    best: u32,
+    solved: u32,
+}
+
+impl Record {
+    fn has_solved(&self, day: i64) -> bool {
+        self.last_solved == Some(day)
+    }
+
+    fn misses(&self, day: i64) -> u32 {
+        if self.day == day {
+            self.misses
+        } else {
+            0
+        }
+    }
+
+    /// The streak is broken once a whole day passes without solving.
+    fn streak(&self, day: i64) -> u32 {
+        match self.last_solved {
+            Some(solved) if solved + 1 >= day => self.streak,
+            _ => 0,
+        }
+    }
+
+    fn miss(&mut self, day: i64) {
+        self.misses = self.misses(day) + 1;
+        self.day = day;
+    }
+
+    fn solve(&mut self, day: i64) {
+        self.streak = self.streak(day) + 1;
+        self.best = self.best.max(self.streak);
+        self.solved += 1;
+        self.last_solved = Some(day);
+    }
+
+    pub(crate) fn as_string(&self) -> String {
+        format!(
+            "デイリー: {}日連続 (最長 {}日, 正解 {}回)",
+            self.streak(schedule::today()),
+            self.best,
+            self.solved
+        )
+    }
+}
+
+/// The seed and the language can be changed by hand in the toml.
+/// Changing the seed changes the words of every day from then on.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Daily {
+    seed: u64,
+    lang: String,
+    /// Keyed by user id since toml keys have to be strings.
+    users: BTreeMap<String, Record>,
+}
+
+impl Default for Daily {
+    fn default() -> Self {
+        Daily {
+            // toml has no unsigned integers, so the seed is kept within i64.
+            seed: u64::from(rand::random::<u32>()),
+            lang: Lang::En.as_code().to_string(),
+            users: BTreeMap::new(),
+        }
+    }
+}
+
+lazy_static! {
+    pub(crate) static ref DAILY: Arc<Mutex<Daily>> =
+        Arc::new(Mutex::new(settings::init_config(DAILY_PATH).unwrap()));
+}
+
+impl Daily {
+    pub(crate) fn get(&self, user: u64) -> Record {
+        self.users
+            .get(&user.to_string())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn lang(&self) -> Lang {
+        Lang::from_code(&self.lang).unwrap_or(Lang::En)
+    }
+
+    /// The word of `day`, the same wherever it is asked.
+    fn word(&self, day: i64) -> String {
+        let mut rng =
+            StdRng::seed_from_u64(self.seed ^ (day as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        bot::get_dictionary(self.lang()).get(&mut rng).to_string()
+    }
+}
+
+fn date(day: i64) -> NaiveDate {
+    NaiveDate::from_num_days_from_ce(day as i32)
+}
+
+/// Shows what is known without the answer: the day, the language, the length and the guesses.
+fn summary(day: i64, lang: Lang, length: usize, record: &Record) -> String {
+    format!(
+        "なぞなぞデイリー {date} ({symbol} {length}文字)\n{marks} {tries}回目で正解\n{streak}日連続正解",
+        date = date(day),
+        symbol = lang.as_symbol(),
+        length = length,
+        marks = "❌".repeat(record.misses(day) as usize) + "⭕",
+        tries = record.misses(day) + 1,
+        streak = record.streak(day),
+    )
+}
+
+fn length(word: &str) -> usize {
+    UnicodeSegmentation::graphemes(word, true).count()
+}
+
+/// The puzzle of today. Its answer is asked in DM, so that it is not spoiled in the channel.
+pub(crate) fn problem(user: u64, in_dm: bool) -> String {
+    let daily = DAILY.lock().unwrap();
+    let day = schedule::today();
+    let (lang, ans) = (daily.lang(), daily.word(day));
+    let record = daily.get(user);
+    let mut response = format!(
+        "なぞなぞデイリー {} ソート前の {} な〜んだ？\n`{}`",
+        date(day),
+        lang.as_symbol(),
+        ans.sorted()
+    );
+    if record.has_solved(day) {
+        response += &format!(
+            "\n本日は解答済みです。\n{}",
+            summary(day, lang, length(&ans), &record)
+        );
+    } else if in_dm {
+        response += "\nこの DM に答えを送ってください。";
+    } else {
+        response += "\n答えは bot への DM で送ってください。正解するまで何度でも答えられます。";
+    }
+    response
+}
+
+/// The spoiler-free result of today, if `user` has solved it.
+pub(crate) fn share(user: u64) -> Option<String> {
+    let daily = DAILY.lock().unwrap();
+    let day = schedule::today();
+    let record = daily.get(user);
+    if record.has_solved(day) {
+        let ans = daily.word(day);
+        Some(summary(day, daily.lang(), length(&ans), &record))
+    } else {
+        None
+    }
+}
+
+/// Checks a guess sent in DM. Each user can solve the puzzle once a day.
+/// While `practicing`, guesses not made of the letters of the puzzle are wrong answers of the practice,
+/// which are ignored.
+pub(crate) fn answer_check(ctx: &mut Context, msg: &Message, practicing: bool) {
+    let daily = &mut *DAILY.lock().unwrap();
+    let day = schedule::today();
+    let (lang, ans) = (daily.lang(), daily.word(day));
+    let dic = bot::get_dictionary(lang);
+    let got = dic.normalize(msg.content.trim());
+    let fits = got.sorted() == ans.sorted();
+    if practicing && !fits {
+        return;
+    }
+    let user = msg.author.id.as_u64().to_string();
+    let record = daily.users.entry(user).or_default();
+    if record.has_solved(day) {
+        try_say!(
+            ctx,
+            msg,
+            "本日のデイリーは解答済みです。`~daily share` で結果を共有できます。"
+        );
+        return;
+    }
+    if !fits {
+        try_say!(ctx, msg, "問題の文字を全て使って答えてください。");
+        return;
+    }
+    let response = if got == *ans || dic.contains(&got) || dic.contains_ex(&got) {
+        record.solve(day);
+        format!(
+            "正解です！\n{}\n`~daily share` で結果を共有できます。",
+            summary(day, lang, length(&ans), record)
+        )
+    } else {
+        record.miss(day);
+        format!("不正解です。 ({}回目)", record.misses(day))
+    };
+    try_say!(ctx, msg, response);
+    if let Err(why) = settings::sync_config(daily, DAILY_PATH) {
+        println!("{}", why);
+    }
+}
@@ -1,26 +1,229 @@
+use super::error::BotError;
+use super::import::{self, Filter};
+use super::index::{self, Index};
+use super::kana;
+use super::normalize::Normalization;
+use super::sort::Sorted;
 use indexmap::IndexSet;
+use itertools::Itertools;
 use rand::distributions::{Distribution, Uniform};
-use serde_derive::{Deserialize, Serialize};
-use std::fs::File;
-use std::io::Read;
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use unicode_segmentation::UnicodeSegmentation;
 
-use std::{env, path::Path};
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+const RETRY_LIMIT: usize = 32;
+
+/// Names of the dictionaries loaded at startup, which are also those compiled by default.
+pub const NAMES: [&str; 7] = [
+    "english",
+    "japanese",
+    "french",
+    "german",
+    "italian",
+    "russian",
+    "esperanto",
+];
+
+/// Also included by the build script, so that the embedded dictionaries are checked against it.
+#[path = "schema.rs"]
+mod schema;
+pub(crate) use schema::RawDictionary;
+pub use schema::{Entry, Form};
+
+/// `dictionaries/*.toml` by their names, validated and embedded by the build script.
+#[cfg(feature = "embed")]
+mod embedded {
+    include!(concat!(env!("OUT_DIR"), "/embedded.rs"));
+}
+
+/// Number of the dictionaries of `NAMES` loaded so far.
+static LOADED: AtomicUsize = AtomicUsize::new(0);
+
+/// Words only accepted as answers, and the anagram table of every word.
+#[derive(Debug)]
+enum Words {
+    Memory {
+        full: Option<IndexSet<String>>,
+        /// Words by their sorted graphemes, as indices of `questions` followed by `full`.
+        /// Words of `full` also in `questions` are only indexed once.
+        anagrams: HashMap<String, Vec<usize>>,
+    },
+    /// Memory-mapped from a file made by `compile`.
+    Compiled(Index),
+}
+
+/// Words asked, in the order of the source.
+#[derive(Debug)]
+enum Questions {
+    Memory(IndexSet<String>),
+    /// Read from the map of a compiled dictionary.
+    Compiled(index::Questions),
+}
+
+impl Questions {
+    fn get(&self, index: usize) -> Option<&str> {
+        match self {
+            Questions::Memory(questions) => questions.get_index(index).map(String::as_str),
+            Questions::Compiled(questions) => questions.get(index),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Questions::Memory(questions) => questions.len(),
+            Questions::Compiled(questions) => questions.len(),
+        }
+    }
+
+    fn position(&self, word: &str) -> Option<usize> {
+        match self {
+            Questions::Memory(questions) => questions.get_full(word).map(|(index, _)| index),
+            Questions::Compiled(questions) => questions.position(word),
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &str> + Clone {
+        (0..self.len()).filter_map(move |index| self.get(index))
+    }
+}
 
 #[derive(Debug)]
 pub struct Dictionary {
-    questions: IndexSet<String>,
-    full: Option<IndexSet<String>>,
+    questions: Questions,
+    normalization: Normalization,
+    words: Words,
     dist: Uniform<usize>,
+    /// Indices of `questions` by their first grapheme, normalized by `kana::normalize`.
+    heads: HashMap<String, Vec<usize>>,
+    entries: HashMap<String, Entry>,
+    /// Indices of `questions` by the tags of their entries.
+    tags: HashMap<String, Vec<usize>>,
 }
-#[derive(Debug, Serialize, Deserialize)]
-struct RawDictionary {
-    questions: Vec<String>,
-    full: Option<Vec<String>>,
+
+impl Entry {
+    /// Shown after the word is solved or given up.
+    pub fn as_string(&self) -> String {
+        let mut lines = Vec::new();
+        if let Some(reading) = &self.reading {
+            lines.push(format!("読み: {}", reading));
+        }
+        match (&self.meaning, &self.pos) {
+            (Some(meaning), Some(pos)) => lines.push(format!("意味: ({}) {}", pos, meaning)),
+            (Some(meaning), None) => lines.push(format!("意味: {}", meaning)),
+            (None, Some(pos)) => lines.push(format!("品詞: {}", pos)),
+            (None, None) => {}
+        }
+        if let Some(source) = &self.source {
+            lines.push(format!("出典: {}", source));
+        }
+        lines.join("\n")
+    }
+}
+
+impl RawDictionary {
+    fn normalization(&self) -> Normalization {
+        let default = Normalization::default();
+        Normalization {
+            form: self.normalization.unwrap_or(default.form),
+            case_fold: self.case_fold.unwrap_or(default.case_fold),
+        }
+    }
 }
 
 impl Dictionary {
-    pub fn get<Rng: rand::Rng>(&self, engine: &mut Rng) -> &String {
-        self.questions.get_index(self.dist.sample(engine)).unwrap()
+    pub fn get<Rng: rand::Rng>(&self, engine: &mut Rng) -> &str {
+        self.questions.get(self.dist.sample(engine)).unwrap()
+    }
+
+    /// Samples a word for which `excluded` returns false, if any exists.
+    pub fn get_except<Rng: rand::Rng, F: Fn(&str) -> bool>(
+        &self,
+        engine: &mut Rng,
+        excluded: F,
+    ) -> Option<&str> {
+        self.get_tagged_except(engine, None, excluded)
+    }
+
+    /// Samples a word with `tag`, or any word without it, if any exists.
+    pub fn get_tagged<Rng: rand::Rng>(&self, engine: &mut Rng, tag: Option<&str>) -> Option<&str> {
+        match tag {
+            None => Some(self.get(engine)),
+            Some(tag) => self
+                .tags
+                .get(tag)?
+                .choose(engine)
+                .map(|index| self.questions.get(*index).unwrap()),
+        }
+    }
+
+    /// Samples a word with `tag` for which `excluded` returns false, if any exists.
+    pub fn get_tagged_except<Rng: rand::Rng, F: Fn(&str) -> bool>(
+        &self,
+        engine: &mut Rng,
+        tag: Option<&str>,
+        excluded: F,
+    ) -> Option<&str> {
+        // rejection sampling is enough unless most of the words are excluded.
+        for _ in 0..RETRY_LIMIT {
+            let word = self.get_tagged(engine, tag)?;
+            if !excluded(word) {
+                return Some(word);
+            }
+        }
+        let candidates = match tag {
+            None => self.questions.iter().collect::<Vec<_>>(),
+            Some(tag) => self.tags[tag]
+                .iter()
+                .map(|index| self.questions.get(*index).unwrap())
+                .collect(),
+        };
+        candidates
+            .into_iter()
+            .filter(|word| !excluded(word))
+            .collect::<Vec<_>>()
+            .choose(engine)
+            .copied()
+    }
+
+    /// Tags with the number of their words, the most common first.
+    pub fn tags(&self) -> Vec<(&str, usize)> {
+        self.tags
+            .iter()
+            .map(|(tag, indices)| (tag.as_str(), indices.len()))
+            .sorted_by_key(|(tag, len)| (std::cmp::Reverse(*len), *tag))
+            .collect()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains_key(tag)
+    }
+
+    /// Samples a word beginning with `head` for which `excluded` returns false, if any exists.
+    pub fn get_starting_with<Rng: rand::Rng, F: Fn(&str) -> bool>(
+        &self,
+        engine: &mut Rng,
+        head: &str,
+        excluded: F,
+    ) -> Option<&str> {
+        self.heads
+            .get(head)?
+            .iter()
+            .map(|index| self.questions.get(*index).unwrap())
+            .filter(|word| !excluded(word))
+            .collect::<Vec<_>>()
+            .choose(engine)
+            .copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.questions.iter()
     }
 
     pub fn len(&self) -> usize {
@@ -28,85 +231,376 @@ impl Dictionary {
     }
 
     pub fn full_len(&self) -> Option<usize> {
-        self.full.as_ref().map(|dic| dic.len())
+        match &self.words {
+            Words::Memory { full, .. } => full.as_ref().map(|dic| dic.len()),
+            Words::Compiled(index) => index.full_len(),
+        }
+    }
+
+    pub fn entry(&self, word: &str) -> Option<&Entry> {
+        self.entries.get(word)
+    }
+
+    /// The entry of `word` as a line following the answer, or nothing if it has none.
+    pub fn describe(&self, word: &str) -> String {
+        match self.entry(word).map(Entry::as_string) {
+            Some(description) if !description.is_empty() => format!("\n{}", description),
+            _ => String::new(),
+        }
+    }
+
+    /// Normalizes a word as the words of the dictionary are.
+    pub fn normalize(&self, word: &str) -> String {
+        self.normalization.apply(word)
     }
 
     pub fn contains(&self, word: &str) -> bool {
-        self.questions.contains(word)
+        self.questions.position(word).is_some()
     }
 
     pub fn contains_ex(&self, word: &str) -> bool {
-        self.full
-            .as_ref()
-            .map(|x| x.contains(word))
-            .unwrap_or(false)
-    }
-
-    pub fn from_toml<S: AsRef<Path>>(file: S) -> std::io::Result<Dictionary> {
-        let mut f = File::open(Path::new(&env::var("DIC_DIR").unwrap()).join(file))?;
-        let mut buffer = String::new();
-        // config file open
-        // read config.toml
-        let _ = f.read_to_string(&mut buffer).unwrap();
-        // parse toml
-        let raw: RawDictionary =
-            toml::from_slice(buffer.as_bytes()).expect("could not parse dictionary!");
+        match &self.words {
+            Words::Memory { full, .. } => full.as_ref().map(|x| x.contains(word)).unwrap_or(false),
+            Words::Compiled(index) => index.contains_full(word),
+        }
+    }
+
+    /// Words of `questions` or `full` consisting of the same graphemes as `sorted`, which must be sorted.
+    pub fn anagrams(&self, sorted: &str) -> Vec<String> {
+        match &self.words {
+            Words::Memory { full, anagrams } => anagrams
+                .get(sorted)
+                .into_iter()
+                .flatten()
+                .filter_map(|index| {
+                    self.questions.get(*index).or_else(|| {
+                        full.as_ref()?
+                            .get_index(*index - self.questions.len())
+                            .map(String::as_str)
+                    })
+                })
+                .map(str::to_string)
+                .collect(),
+            Words::Compiled(index) => index.anagrams(sorted),
+        }
+    }
+
+    /// Words of `questions` or `full` made of some of the graphemes of `word`, each used at most once,
+    /// with at least `min_len` graphemes.
+    /// Every sub-multiset of the graphemes is looked up, so `word` should be a short one.
+    pub fn sub_anagrams(&self, word: &str, min_len: usize) -> Vec<String> {
+        let sorted = word.to_string().sorted();
+        let graphemes = UnicodeSegmentation::graphemes(sorted.as_str(), true)
+            .group_by(|grapheme| *grapheme)
+            .into_iter()
+            .map(|(grapheme, group)| (grapheme, group.count()))
+            .collect::<Vec<_>>();
+        let mut found = Vec::new();
+        self.collect_sub_anagrams(&graphemes, String::new(), 0, min_len, &mut found);
+        found
+    }
+
+    fn collect_sub_anagrams(
+        &self,
+        graphemes: &[(&str, usize)],
+        key: String,
+        len: usize,
+        min_len: usize,
+        found: &mut Vec<String>,
+    ) {
+        match graphemes.split_first() {
+            None if len >= min_len => found.extend(self.anagrams(&key)),
+            None => {}
+            Some(((grapheme, count), rest)) => {
+                for used in 0..=*count {
+                    self.collect_sub_anagrams(
+                        rest,
+                        key.clone() + &grapheme.repeat(used),
+                        len + used,
+                        min_len,
+                        found,
+                    );
+                }
+            }
+        }
+    }
+
+    /// The word list of `name` in `dir`, in the first format of `import::EXTENSIONS` found.
+    fn source(dir: &Path, name: &str) -> Option<PathBuf> {
+        import::EXTENSIONS
+            .iter()
+            .map(|extension| dir.join(format!("{}.{}", name, extension)))
+            .find(|path| path.exists())
+    }
+
+    /// Loads `<name>.idx` of `DIC_DIR` if it is newer than the word list of `name`,
+    /// or else `<name>.toml` or a word list of another format with the same name.
+    /// Without `DIC_DIR`, the dictionary embedded by the `embed` feature is loaded.
+    pub fn load(name: &str) -> Result<Dictionary, BotError> {
+        let dir = match env::var_os("DIC_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => return Dictionary::embedded(name),
+        };
+        let source = Dictionary::source(&dir, name);
+        let compiled = dir.join(format!("{}.{}", name, index::EXTENSION));
+        let modified = |path: &Path| path.metadata().and_then(|meta| meta.modified()).ok();
+        let is_fresh = match (modified(&compiled), source.as_deref().and_then(modified)) {
+            (Some(compiled), Some(source)) => compiled >= source,
+            (compiled, _) => compiled.is_some(),
+        };
+        if is_fresh {
+            // an index of an older version is ignored until it is compiled again.
+            match Index::open(&compiled) {
+                Ok(index) => return Ok(Dictionary::from_index(index)),
+                Err(why) => println!("{}", why),
+            }
+        }
+        let source = source.ok_or_else(|| BotError::NoDictionary(name.to_string()))?;
+        Dictionary::from_raw(import::read(&source, &Filter::default())?)
+    }
+
+    #[cfg(feature = "embed")]
+    fn embedded(name: &str) -> Result<Dictionary, BotError> {
+        let (_, buffer) = embedded::EMBEDDED
+            .iter()
+            .find(|(embedded, _)| *embedded == name)
+            .ok_or_else(|| BotError::NoDictionary(name.to_string()))?;
+        Dictionary::from_raw(toml::from_str(buffer)?)
+    }
+
+    #[cfg(not(feature = "embed"))]
+    fn embedded(_name: &str) -> Result<Dictionary, BotError> {
+        Err(BotError::NoDicDir)
+    }
+
+    /// Compiles the word list of `name` in `DIC_DIR` into `<name>.idx` beside it.
+    pub fn compile(name: &str) -> Result<PathBuf, BotError> {
+        let dir = PathBuf::from(env::var_os("DIC_DIR").ok_or(BotError::NoDicDir)?);
+        let source = Dictionary::source(&dir, name)
+            .ok_or_else(|| BotError::NoDictionary(name.to_string()))?;
+        let dictionary = Dictionary::from_raw(import::read(&source, &Filter::default())?)?;
+        let full = match &dictionary.words {
+            Words::Memory { full, .. } => full,
+            Words::Compiled(_) => unreachable!(),
+        };
+        let path = dir.join(format!("{}.{}", name, index::EXTENSION));
+        index::write(
+            &path,
+            dictionary.questions.iter(),
+            full.as_ref(),
+            dictionary.normalization,
+            dictionary
+                .entries
+                .values()
+                .cloned()
+                .sorted_by(|a, b| a.word.cmp(&b.word))
+                .collect(),
+        )?;
+        Ok(path)
+    }
+
+    fn from_index(
+        (index, questions, entries): (Index, index::Questions, Vec<Entry>),
+    ) -> Dictionary {
+        let entries = entries
+            .into_iter()
+            .map(|entry| (entry.word.clone(), entry))
+            .collect();
+        Dictionary::new(
+            Questions::Compiled(questions),
+            entries,
+            index.normalization(),
+            Words::Compiled(index),
+        )
+    }
+
+    /// Fails if no questions are left, such as when every word is filtered out.
+    pub(crate) fn from_raw(raw: RawDictionary) -> Result<Dictionary, BotError> {
+        let normalization = raw.normalization();
+        // empty words are dropped, since no puzzle can be made of them.
         let mut questions = IndexSet::new();
         for word in raw.questions {
-            questions.insert(word.to_lowercase());
+            let word = normalization.apply(&word);
+            if !word.is_empty() {
+                questions.insert(word);
+            }
+        }
+        let mut entries = HashMap::new();
+        for mut entry in raw.entries {
+            entry.word = normalization.apply(&entry.word);
+            if entry.word.is_empty() {
+                continue;
+            }
+            questions.insert(entry.word.clone());
+            entries.insert(entry.word.clone(), entry);
         }
         let full = if let Some(full) = raw.full {
             let mut full_dic = IndexSet::new();
             for word in full {
-                full_dic.insert(word.to_lowercase());
+                let word = normalization.apply(&word);
+                if !word.is_empty() {
+                    full_dic.insert(word);
+                }
             }
             Some(full_dic)
         } else {
             None
         };
+        if questions.is_empty() {
+            return Err(BotError::NoQuestions);
+        }
+        let mut anagrams = HashMap::<String, Vec<usize>>::new();
+        for (index, word) in questions.iter().chain(full.iter().flatten()).enumerate() {
+            if index >= questions.len() && questions.contains(word) {
+                continue;
+            }
+            anagrams.entry(word.sorted()).or_default().push(index);
+        }
+        Ok(Dictionary::new(
+            Questions::Memory(questions),
+            entries,
+            normalization,
+            Words::Memory { full, anagrams },
+        ))
+    }
+
+    fn new(
+        questions: Questions,
+        entries: HashMap<String, Entry>,
+        normalization: Normalization,
+        words: Words,
+    ) -> Dictionary {
+        let mut tags = HashMap::<String, Vec<usize>>::new();
+        for entry in entries.values() {
+            let index = questions.position(&entry.word).unwrap();
+            for tag in entry.tags.iter() {
+                tags.entry(tag.to_lowercase()).or_default().push(index);
+            }
+        }
+        // entries are in a hash map, so the indices are sorted to sample tagged words reproducibly.
+        for indices in tags.values_mut() {
+            indices.sort();
+            indices.dedup();
+        }
         let dist = Uniform::new(0, questions.len());
-        Ok(Dictionary {
+        let mut heads = HashMap::<String, Vec<usize>>::new();
+        for (index, word) in questions.iter().enumerate() {
+            if let Some(head) = kana::head(word) {
+                heads.entry(head).or_default().push(index);
+            }
+        }
+        Dictionary {
             questions,
-            full,
+            normalization,
+            words,
             dist,
-        })
+            heads,
+            entries,
+            tags,
+        }
     }
 }
 
+/// Loads a dictionary of `NAMES`, or exits, since every quiz of its language would fail without it
+/// and the bot would wait for it forever.
+fn load_static(name: &str) -> Dictionary {
+    let dictionary = Dictionary::load(name).unwrap_or_else(|why| {
+        println!("failed to load {}: {}", name, why);
+        std::process::exit(1)
+    });
+    println!(
+        "{} is loaded: len = {}",
+        name.to_uppercase(),
+        dictionary.questions.len()
+    );
+    LOADED.fetch_add(1, Ordering::SeqCst);
+    dictionary
+}
+
 lazy_static! {
-    pub static ref ENGLISH: Dictionary = {
-        let dictionary: Dictionary = Dictionary::from_toml("english.toml").unwrap();
-        println!("ENGLISH is loaded: len = {}", dictionary.questions.len());
-        dictionary
-    };
-    pub static ref JAPANESE: Dictionary = {
-        let dictionary: Dictionary = Dictionary::from_toml("japanese.toml").unwrap();
-        println!("JAPANESE is loaded: len = {}", dictionary.questions.len());
-        dictionary
-    };
-    pub static ref FRENCH: Dictionary = {
-        let dictionary: Dictionary = Dictionary::from_toml("french.toml").unwrap();
-        println!("FRENCH is loaded: len = {}", dictionary.questions.len());
-        dictionary
-    };
-    pub static ref GERMAN: Dictionary = {
-        let dictionary: Dictionary = Dictionary::from_toml("german.toml").unwrap();
-        println!("GERMAN is loaded: len = {}", dictionary.questions.len());
-        dictionary
-    };
-    pub static ref ITALIAN: Dictionary = {
-        let dictionary: Dictionary = Dictionary::from_toml("italian.toml").unwrap();
-        println!("ITALIAN is loaded: len = {}", dictionary.questions.len());
-        dictionary
-    };
-    pub static ref RUSSIAN: Dictionary = {
-        let dictionary: Dictionary = Dictionary::from_toml("russian.toml").unwrap();
-        println!("RUSSIAN is loaded: len = {}", dictionary.questions.len());
-        dictionary
-    };
-    pub static ref ESPERANTO: Dictionary = {
-        let dictionary: Dictionary = Dictionary::from_toml("esperanto.toml").unwrap();
-        println!("ESPERANTO is loaded: len = {}", dictionary.questions.len());
-        dictionary
-    };
+    pub static ref ENGLISH: Dictionary = load_static("english");
+    pub static ref JAPANESE: Dictionary = load_static("japanese");
+    pub static ref FRENCH: Dictionary = load_static("french");
+    pub static ref GERMAN: Dictionary = load_static("german");
+    pub static ref ITALIAN: Dictionary = load_static("italian");
+    pub static ref RUSSIAN: Dictionary = load_static("russian");
+    pub static ref ESPERANTO: Dictionary = load_static("esperanto");
+}
+
+/// Loads every dictionary in the background at startup, instead of on the first quiz of its language.
+pub fn preload() {
+    let loaders: [fn(); 7] = [
+        || lazy_static::initialize(&ENGLISH),
+        || lazy_static::initialize(&JAPANESE),
+        || lazy_static::initialize(&FRENCH),
+        || lazy_static::initialize(&GERMAN),
+        || lazy_static::initialize(&ITALIAN),
+        || lazy_static::initialize(&RUSSIAN),
+        || lazy_static::initialize(&ESPERANTO),
+    ];
+    for loader in loaders.iter().copied() {
+        thread::spawn(move || {
+            // a panic while loading leaves the dictionary unusable, just as an error does.
+            if std::panic::catch_unwind(loader).is_err() {
+                std::process::exit(1);
+            }
+        });
+    }
+}
+
+/// Whether every dictionary has been loaded by `preload`.
+pub fn is_ready() -> bool {
+    LOADED.load(Ordering::SeqCst) == NAMES.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unicode_normalization::UnicodeNormalization;
+
+    /// The dictionary `name` of the repository, read without `DIC_DIR`.
+    fn repository(name: &str) -> Dictionary {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("dictionaries")
+            .join(format!("{}.toml", name));
+        Dictionary::from_raw(import::read(&path, &Filter::default()).unwrap()).unwrap()
+    }
+
+    fn decompose(word: &str) -> String {
+        word.nfd().collect()
+    }
+
+    #[test]
+    fn words_of_dictionaries_are_normalized() {
+        for name in NAMES.iter() {
+            let dic = repository(name);
+            let full = match &dic.words {
+                Words::Memory { full, .. } => full.iter().flatten().map(String::as_str),
+                Words::Compiled(_) => unreachable!(),
+            };
+            for word in dic.iter().chain(full) {
+                assert!(!word.is_empty(), "{}", name);
+                assert_eq!(dic.normalize(word), word, "{}", name);
+                assert_eq!(dic.normalize(&decompose(word)), word, "{}", name);
+                let sorted = word.to_string().sorted();
+                assert_eq!(sorted, decompose(word).sorted(), "{}", name);
+                assert_eq!(
+                    sorted.graphemes(true).count(),
+                    word.graphemes(true).count(),
+                    "{}",
+                    name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn decomposed_answers_are_correct() {
+        for (name, letter) in [("esperanto", 'ĉ'), ("japanese", 'が')].iter() {
+            let dic = repository(name);
+            let ans = dic.iter().find(|word| word.contains(*letter)).unwrap();
+            assert_eq!(dic.normalize(&decompose(ans)), ans);
+            assert_eq!(dic.normalize(&ans.to_uppercase()), ans);
+        }
+    }
 }
@@ -0,0 +1,259 @@
+use super::bot::{self, Lang, Status};
+use super::dictionary::Dictionary;
+use super::history;
+use super::sort::Sorted;
+use crate::try_say;
+use indexmap::IndexMap;
+use serenity::client::Context;
+use serenity::model::channel::Message;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::time::Instant;
+use unicode_segmentation::UnicodeSegmentation;
+
+const RETRY_LIMIT: usize = 64;
+
+/// Each of the two words has at least this many graphemes.
+const MIN_WORD_LENGTH: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    /// Total number of graphemes of the two words.
+    fn length(self) -> RangeInclusive<usize> {
+        match self {
+            Difficulty::Easy => 6..=8,
+            Difficulty::Normal => 9..=11,
+            Difficulty::Hard => 12..=14,
+        }
+    }
+}
+
+fn length(word: &str) -> usize {
+    UnicodeSegmentation::graphemes(word, true).count()
+}
+
+fn counts(word: &str) -> HashMap<&str, usize> {
+    let mut counts = HashMap::new();
+    for grapheme in UnicodeSegmentation::graphemes(word, true) {
+        *counts.entry(grapheme).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// The graphemes of `sorted` left after removing those of `word`, sorted,
+/// or `None` if `word` is not made of them.
+fn remove(sorted: &str, word: &str) -> Option<String> {
+    let mut rest = counts(word);
+    let remainder = UnicodeSegmentation::graphemes(sorted, true)
+        .filter(|grapheme| match rest.get_mut(grapheme) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        })
+        .collect::<String>();
+    if rest.values().all(|count| *count == 0) {
+        Some(remainder)
+    } else {
+        None
+    }
+}
+
+fn is_word(dic: &Dictionary, word: &str) -> bool {
+    dic.contains(word) || dic.contains_ex(word)
+}
+
+/// Whether `sorted` splits into `word` and another word of the dictionary.
+fn completes(dic: &Dictionary, sorted: &str, word: &str) -> bool {
+    is_word(dic, word) && remove(sorted, word).map_or(false, |rest| !dic.anagrams(&rest).is_empty())
+}
+
+/// Every split of `sorted` into two words, each counted once regardless of the order.
+pub fn splits(dic: &Dictionary, sorted: &str) -> Vec<(String, String)> {
+    let mut splits = Vec::new();
+    for first in dic.sub_anagrams(sorted, MIN_WORD_LENGTH) {
+        let rest = match remove(sorted, &first) {
+            Some(rest) => rest,
+            None => continue,
+        };
+        for second in dic.anagrams(&rest) {
+            if first <= second && length(&second) >= MIN_WORD_LENGTH {
+                splits.push((first.clone(), second));
+            }
+        }
+    }
+    splits
+}
+
+/// Two words merged into one puzzle. Players name both words, in one message or two.
+#[derive(Debug)]
+pub struct Double {
+    pub lang: Lang,
+    /// The intended pair of words.
+    pub words: (String, String),
+    /// The merged graphemes, sorted.
+    pub sorted: String,
+    /// The first word named by each player.
+    pub partial: IndexMap<u64, String>,
+    pub started: Instant,
+}
+
+impl Double {
+    /// Whether the two words together make the puzzle.
+    fn is_solved_by(&self, first: &str, second: &str) -> bool {
+        let dic = bot::get_dictionary(self.lang);
+        is_word(dic, first)
+            && is_word(dic, second)
+            && remove(&self.sorted, first).map_or(false, |rest| rest == second.to_string().sorted())
+    }
+}
+
+/// Picks two words of `questions` whose total length is in the range of `difficulty`.
+fn pick(lang: Lang, difficulty: Difficulty) -> Option<(String, String)> {
+    let dic = bot::get_dictionary(lang);
+    let range = difficulty.length();
+    let rng = &mut rand::thread_rng();
+    for _ in 0..RETRY_LIMIT {
+        let first = dic.get_except(rng, |word| {
+            let len = length(word);
+            len < MIN_WORD_LENGTH || len + MIN_WORD_LENGTH > *range.end()
+        })?;
+        let len = length(first);
+        let second = dic.get_except(rng, |word| {
+            word == first
+                || length(word) < MIN_WORD_LENGTH
+                || !range.contains(&(len + length(word)))
+        });
+        if let Some(second) = second {
+            return Some((first.to_string(), second.to_string()));
+        }
+    }
+    None
+}
+
+pub(crate) fn start(
+    ctx: &mut Context,
+    msg: &Message,
+    quiz: &mut Status,
+    lang: Lang,
+    difficulty: Difficulty,
+) {
+    let (first, second) = match pick(lang, difficulty) {
+        Some(words) => words,
+        None => {
+            try_say!(ctx, msg, "条件に合う単語の組が見つかりませんでした。");
+            return;
+        }
+    };
+    for word in [&first, &second].iter() {
+        if let Err(why) = history::record(*msg.channel_id.as_u64(), lang, word) {
+            println!("{}", why);
+        }
+    }
+    let sorted = (first.clone() + &second).sorted();
+    try_say!(
+        ctx,
+        msg,
+        format!(
+            "ダブルアナグラム 2つの {symbol} を混ぜてソートしました。元の2語な〜んだ？\n`{prob}`\n2語をまとめて、または1語ずつ答えてください。",
+            symbol = lang.as_symbol(),
+            prob = sorted,
+        )
+    );
+    *quiz = Status::Doubling(Double {
+        lang,
+        words: (first, second),
+        sorted,
+        partial: IndexMap::new(),
+        started: Instant::now(),
+    });
+}
+
+/// Accepts both words in a message, or one word which completes the word the author named before.
+pub(crate) fn answer_check(ctx: &mut Context, msg: &Message, quiz: &mut Status) {
+    let double = match quiz {
+        Status::Doubling(double) => double,
+        _ => return,
+    };
+    let dic = bot::get_dictionary(double.lang);
+    let words = msg
+        .content
+        .split_whitespace()
+        .map(|word| dic.normalize(word))
+        .collect::<Vec<_>>();
+    let author = *msg.author.id.as_u64();
+    let solved = match words.as_slice() {
+        [first, second] if double.is_solved_by(first, second) => (first.clone(), second.clone()),
+        [word] => match double.partial.get(&author) {
+            Some(first) if double.is_solved_by(first, word) => (first.clone(), word.clone()),
+            _ => {
+                if completes(bot::get_dictionary(double.lang), &double.sorted, word) {
+                    double.partial.insert(author, word.clone());
+                    try_say!(
+                        ctx,
+                        msg,
+                        format!(
+                            "{} さん、\"{}\" を受け付けました。残りの1語は？",
+                            &msg.author.name, word
+                        )
+                    );
+                }
+                return;
+            }
+        },
+        _ => return,
+    };
+    let elapsed = double.started.elapsed().as_secs_f32();
+    let (first, second) = &double.words;
+    let expected =
+        (solved.0 == *first && solved.1 == *second) || (solved.0 == *second && solved.1 == *first);
+    try_say!(
+        ctx,
+        msg,
+        if expected {
+            format!(
+                "{} さん、正解です！\n正解は\"{}\"と\"{}\"でした！ [{:.3} sec]",
+                &msg.author.name, first, second, elapsed
+            )
+        } else {
+            format!(
+                "{} さん、\"{}\"と\"{}\"は非想定解ですが正解です！\n想定解は\"{}\"と\"{}\"でした。 [{:.3} sec]",
+                &msg.author.name, solved.0, solved.1, first, second, elapsed
+            )
+        }
+    );
+    *quiz = Status::StandingBy;
+}
+
+/// Shows the intended pair and every other split.
+pub(crate) fn giveup(ctx: &mut Context, msg: &Message, quiz: &mut Status) {
+    if let Status::Doubling(double) = std::mem::replace(quiz, Status::StandingBy) {
+        let (first, second) = &double.words;
+        let others = splits(bot::get_dictionary(double.lang), &double.sorted)
+            .into_iter()
+            .filter(|(a, b)| !((a == first && b == second) || (a == second && b == first)))
+            .map(|(a, b)| format!("{} + {}", a, b))
+            .collect::<Vec<_>>();
+        let mut response = format!("正解は \"{}\" と \"{}\" でした...", first, second);
+        if !others.is_empty() {
+            response += &format!("\n別解: {}", others.join(", "));
+        }
+        try_say!(ctx, msg, response);
+    }
+}
@@ -0,0 +1,419 @@
+use super::bot::{self, ContestData, DictionarySelector};
+use super::history;
+use super::puzzle;
+use super::stats;
+use super::tournament;
+use serenity::http::Http;
+use serenity::model::channel::{Message, Reaction, ReactionType};
+use serenity::model::id::ChannelId;
+use std::cmp::Ordering;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// An invitation which is not accepted within this period is discarded.
+const INVITATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub(crate) const ACCEPT_REACTION: char = '✅';
+
+#[derive(Debug)]
+pub(crate) struct Player {
+    pub(crate) id: u64,
+    pub(crate) name: String,
+    pub(crate) result: ContestData,
+}
+
+impl Player {
+    pub(crate) fn new(id: u64, name: String) -> Self {
+        Player {
+            id,
+            name,
+            result: ContestData::default(),
+        }
+    }
+
+    fn score(&self) -> u32 {
+        self.result.time.len() as u32
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum Phase {
+    /// Waiting for the opponent to accept the invitation posted as the message.
+    Inviting(u64, Instant),
+    /// The quiz of the duel, which is always `Status::Contesting`.
+    Playing(bot::Status),
+}
+
+/// A two-player match held in a channel alongside its normal quiz.
+pub(crate) struct Duel {
+    pub(crate) channel: u64,
+    /// The challenger and the opponent.
+    pub(crate) players: [Player; 2],
+    pub(crate) num: u32,
+    pub(crate) target: u32,
+    pub(crate) phase: Phase,
+    /// The generation of the tournament and its bracket match decided by this duel.
+    pub(crate) tournament: Option<(u32, u32)>,
+    library: DictionarySelector,
+    /// The puzzle chosen for the channel by `~puzzle`.
+    kind: puzzle::Kind,
+}
+
+lazy_static! {
+    pub(crate) static ref DUELS: Arc<Mutex<Vec<Duel>>> = Arc::new(Mutex::new(Vec::new()));
+}
+
+fn say(http: &Http, channel: ChannelId, response: String) -> Option<Message> {
+    channel
+        .say(http, response)
+        .map_err(|why| println!("{}", why))
+        .ok()
+}
+
+impl Duel {
+    pub(crate) fn new(
+        channel: u64,
+        players: [Player; 2],
+        num: u32,
+        target: u32,
+        languages: Vec<(String, u32)>,
+    ) -> Self {
+        let mut library = DictionarySelector::new();
+        library.set(languages, false, rand::random(), None);
+        Duel {
+            channel,
+            players,
+            num,
+            target,
+            phase: Phase::Inviting(0, Instant::now()),
+            tournament: None,
+            library,
+            kind: puzzle::channel_default(channel),
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        match self.phase {
+            Phase::Inviting(_, at) => at.elapsed() > INVITATION_TIMEOUT,
+            Phase::Playing(_) => false,
+        }
+    }
+
+    fn scores(&self) -> String {
+        format!(
+            "{} {} - {} {}",
+            self.players[0].name,
+            self.players[0].score(),
+            self.players[1].score(),
+            self.players[1].name
+        )
+    }
+
+    /// Posts the next problem. `count` is the number of problems asked so far.
+    fn next_problem(&mut self, http: &Http, count: u32) {
+        let (ans, lang) = self.library.next();
+        let puzzle = self.kind.make(
+            &ans,
+            bot::get_dictionary(lang),
+            &mut self.library.puzzle_rng(count + 1),
+        );
+        if let Err(why) = history::record(self.channel, lang, &ans) {
+            println!("{}", why);
+        }
+        say(
+            http,
+            ChannelId::from(self.channel),
+            format!(
+                "決闘 問 {current} ({current}/{number}) {scores}\nソートなぞなぞ {statement} {symbol} な〜んだ？\n`{prob}`",
+                current = count + 1,
+                number = self.num,
+                scores = self.scores(),
+                statement = self.kind.statement(),
+                symbol = lang.as_symbol(),
+                prob = puzzle.display(&ans),
+            ),
+        );
+        self.phase = Phase::Playing(bot::Status::Contesting(
+            ans,
+            lang,
+            (count + 1, self.num),
+            Instant::now(),
+            puzzle,
+        ));
+    }
+
+    fn begin(&mut self, http: &Http) {
+        say(
+            http,
+            ChannelId::from(self.channel),
+            format!("決闘を開始します！ {}", self.scores()),
+        );
+        self.next_problem(http, 0);
+    }
+
+    fn is_over(&self, count: u32) -> bool {
+        count >= self.num
+            || self
+                .players
+                .iter()
+                .any(|player| player.score() >= self.target)
+    }
+
+    /// Announces the result and records it in the statistics of both players.
+    /// Returns the duels of the bracket matches which the result made playable,
+    /// checking the players against the other `duels`.
+    fn finish(&self, http: &Http, resigned: Option<u64>, duels: &[Duel]) -> Vec<Duel> {
+        // the result of the challenger against the opponent.
+        let order = match resigned {
+            Some(id) if id == self.players[0].id => Ordering::Less,
+            Some(_) => Ordering::Greater,
+            None => match self.players[0].score().cmp(&self.players[1].score()) {
+                // a bracket match needs a winner: the faster one, or the first player of the match.
+                Ordering::Equal if self.tournament.is_some() => self.players[1]
+                    .result
+                    .key()
+                    .cmp(&self.players[0].result.key())
+                    .then(Ordering::Greater),
+                order => order,
+            },
+        };
+        let verdict = match order {
+            Ordering::Less => format!("{} さんの勝利です！", self.players[1].name),
+            Ordering::Greater => format!("{} さんの勝利です！", self.players[0].name),
+            Ordering::Equal => "引き分けです。".to_string(),
+        };
+        say(
+            http,
+            ChannelId::from(self.channel),
+            format!("決闘が終了しました。\n{}\n{}", self.scores(), verdict),
+        );
+        let ids = [self.players[0].id, self.players[1].id];
+        let result = stats::update(&ids, |id, stats| {
+            let player = &self.players[if id == ids[0] { 0 } else { 1 }];
+            stats.duels += 1;
+            stats.solved += player.score();
+            let won = if id == ids[0] { order } else { order.reverse() };
+            match won {
+                Ordering::Greater => stats.wins += 1,
+                Ordering::Less => stats.losses += 1,
+                Ordering::Equal => stats.draws += 1,
+            }
+        });
+        if let Err(why) = result {
+            println!("{}", why);
+        }
+        match self.tournament {
+            Some((generation, id)) => {
+                let winner = if order == Ordering::Greater {
+                    ids[0]
+                } else {
+                    ids[1]
+                };
+                tournament::report(http, generation, id, winner, duels)
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Ends the duel at `position` of `duels`, and begins the bracket matches following it.
+fn end(http: &Http, duels: &mut Vec<Duel>, position: usize, resigned: Option<u64>) {
+    let duel = duels.remove(position);
+    let matches = duel.finish(http, resigned, duels);
+    begin_matches(http, duels, matches);
+}
+
+fn begin_matches(http: &Http, duels: &mut Vec<Duel>, matches: Vec<Duel>) {
+    for mut duel in matches {
+        duel.begin(http);
+        duels.push(duel);
+    }
+}
+
+/// Starts the playable matches of the tournament in its channel.
+pub(crate) fn start_matches(http: &Http) {
+    let duels = &mut *DUELS.lock().unwrap();
+    let matches = tournament::start_matches(http, duels);
+    begin_matches(http, duels, matches);
+}
+
+/// Whether `user` is playing or invited to a duel.
+pub(crate) fn is_dueling(duels: &[Duel], user: u64) -> bool {
+    duels
+        .iter()
+        .any(|duel| !duel.is_expired() && duel.players.iter().any(|player| player.id == user))
+}
+
+/// Posts the invitation of `duel` and waits for the opponent.
+pub(crate) fn invite(http: &Http, mut duel: Duel) {
+    let duels = &mut *DUELS.lock().unwrap();
+    duels.retain(|duel| !duel.is_expired());
+    let invitation = say(
+        http,
+        ChannelId::from(duel.channel),
+        format!(
+            "{challenger} さんが <@{opponent}> さんに決闘を申し込みました！ ({num}問, {target}問先取)\n`~accept` するかこのメッセージに {reaction} を付けると開始します。",
+            challenger = duel.players[0].name,
+            opponent = duel.players[1].id,
+            num = duel.num,
+            target = duel.target,
+            reaction = ACCEPT_REACTION,
+        ),
+    );
+    if let Some(invitation) = invitation {
+        if let Err(why) =
+            invitation
+                .channel_id
+                .create_reaction(http, invitation.id, ACCEPT_REACTION)
+        {
+            println!("{}", why);
+        }
+        duel.phase = Phase::Inviting(*invitation.id.as_u64(), Instant::now());
+        duels.push(duel);
+    }
+}
+
+/// Starts the duel to which `user` is invited in `channel`, if any.
+/// With `message`, only the duel invited by that message is accepted.
+pub(crate) fn accept(http: &Http, channel: ChannelId, user: u64, message: Option<u64>) -> bool {
+    let duels = &mut *DUELS.lock().unwrap();
+    duels.retain(|duel| !duel.is_expired());
+    let duel = duels.iter_mut().find(|duel| match duel.phase {
+        Phase::Inviting(invitation, _) => {
+            duel.channel == *channel.as_u64()
+                && duel.players[1].id == user
+                && message.map_or(true, |message| message == invitation)
+        }
+        Phase::Playing(_) => false,
+    });
+    match duel {
+        Some(duel) => {
+            duel.begin(http);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Starts `duel` without an invitation.
+pub(crate) fn start(http: &Http, mut duel: Duel) {
+    let duels = &mut *DUELS.lock().unwrap();
+    duel.begin(http);
+    duels.push(duel);
+}
+
+pub(crate) fn accept_reaction(http: &Http, reaction: &Reaction) {
+    match &reaction.emoji {
+        ReactionType::Unicode(emoji) if *emoji == ACCEPT_REACTION.to_string() => {
+            accept(
+                http,
+                reaction.channel_id,
+                *reaction.user_id.as_u64(),
+                Some(*reaction.message_id.as_u64()),
+            );
+        }
+        _ => {}
+    }
+}
+
+/// Checks the message against the duel its author is playing in the channel.
+/// Returns false if the message is not a correct answer of a duel.
+pub(crate) fn answer_check(http: &Http, msg: &Message) -> bool {
+    let duels = &mut *DUELS.lock().unwrap();
+    let author = *msg.author.id.as_u64();
+    let position = duels.iter().position(|duel| {
+        duel.channel == *msg.channel_id.as_u64()
+            && duel.players.iter().any(|player| player.id == author)
+    });
+    let duel = match position {
+        Some(position) => &mut duels[position],
+        None => return false,
+    };
+    let (ans, elapsed, count) = match &duel.phase {
+        Phase::Playing(quiz) => match quiz.answer_check(&msg.content) {
+            bot::CheckResult::WA => return false,
+            _ => (
+                quiz.ans().unwrap().clone(),
+                quiz.elapsed().unwrap(),
+                *quiz.get_contest_num().unwrap().0,
+            ),
+        },
+        Phase::Inviting(..) => return false,
+    };
+    let player = duel
+        .players
+        .iter_mut()
+        .find(|player| player.id == author)
+        .unwrap();
+    player.result += elapsed;
+    say(
+        http,
+        msg.channel_id,
+        format!(
+            "{} さん、正解です！\n正解は\"{}\"でした！ [{:.3} sec]",
+            msg.author.name, ans, elapsed
+        ),
+    );
+    if duel.is_over(count) {
+        end(http, duels, position.unwrap(), None);
+    } else {
+        duel.next_problem(http, count);
+    }
+    true
+}
+
+/// Skips the current problem of the duel `user` is playing in `channel`.
+pub(crate) fn giveup(http: &Http, channel: ChannelId, user: u64) -> bool {
+    let duels = &mut *DUELS.lock().unwrap();
+    let position = duels.iter().position(|duel| {
+        duel.channel == *channel.as_u64()
+            && duel.players.iter().any(|player| player.id == user)
+            && !duel.is_expired()
+    });
+    let duel = match position {
+        Some(position) => &mut duels[position],
+        None => return false,
+    };
+    let (ans, count) = match &duel.phase {
+        Phase::Playing(quiz) => (
+            quiz.ans().unwrap().clone(),
+            *quiz.get_contest_num().unwrap().0,
+        ),
+        Phase::Inviting(..) => {
+            duels.remove(position.unwrap());
+            say(
+                http,
+                channel,
+                "決闘の申し込みを取り消しました。".to_string(),
+            );
+            return true;
+        }
+    };
+    say(http, channel, format!("正解は \"{}\" でした...", ans));
+    if duel.is_over(count) {
+        end(http, duels, position.unwrap(), None);
+    } else {
+        duel.next_problem(http, count);
+    }
+    true
+}
+
+/// Ends the duel `user` is playing in `channel` as a loss of `user`.
+pub(crate) fn resign(http: &Http, channel: ChannelId, user: u64) -> bool {
+    let duels = &mut *DUELS.lock().unwrap();
+    let position = duels.iter().position(|duel| {
+        duel.channel == *channel.as_u64()
+            && duel.players.iter().any(|player| player.id == user)
+            && match duel.phase {
+                Phase::Playing(_) => true,
+                Phase::Inviting(..) => false,
+            }
+    });
+    match position {
+        Some(position) => {
+            end(http, duels, position, Some(user));
+            true
+        }
+        None => false,
+    }
+}
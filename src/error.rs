@@ -13,5 +13,58 @@ quick_error! {
             display("Parse error: {} => {}", err, s)
             context(s: &'static str, err: toml::ser::Error) -> (s, err)
         }
+        Deserialize(err: toml::de::Error) {
+            display("Deserialize error: {}", err)
+            from()
+        }
+        Json(err: serde_json::Error) {
+            display("JSON error: {}", err)
+            from()
+        }
+        Csv(err: csv::Error) {
+            display("CSV error: {}", err)
+            from()
+        }
+        Index(err: fst::Error) {
+            display("index error: {}", err)
+            from()
+        }
+        Discord(err: serenity::Error) {
+            display("Discord error: {}", err)
+            from()
+        }
+        NoProblemSet {
+            display("no problem set is attached or registered")
+        }
+        EmptyProblemSet {
+            display("problem set is empty")
+        }
+        InvalidLine(line: usize, content: String) {
+            display("line {} is not `<lang> <answer>`: {}", line, content)
+        }
+        UnknownLanguage(lang: String) {
+            display("unexpected language '{}'", lang)
+        }
+        UnknownFormat(path: String) {
+            display("unknown format of word list: {}", path)
+        }
+        UnknownEncoding(name: String) {
+            display("unknown encoding of Hunspell affixes: {}", name)
+        }
+        InvalidEncoding(path: String) {
+            display("not encoded in UTF-8 or the encoding given by SET: {}", path)
+        }
+        NoDictionary(name: String) {
+            display("no dictionary '{}' in DIC_DIR", name)
+        }
+        NoQuestions {
+            display("no questions are left in the dictionary")
+        }
+        NoDicDir {
+            display("DIC_DIR is not set, and no dictionary is embedded (see the `embed` feature)")
+        }
+        InvalidIndex(path: String) {
+            display("not a dictionary index of this version: {}", path)
+        }
     }
 }
@@ -0,0 +1,98 @@
+use super::bot::Lang;
+use super::error::BotError;
+use super::settings;
+use indexmap::IndexSet;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HISTORY_PATH: &str = "/tmp/settings/history.toml";
+
+/// Words older than this are forgotten on the next write.
+pub(crate) const RETENTION_DAYS: u64 = 90;
+
+/// The file is rewritten once this many words are recorded, rather than on every problem.
+/// Words not written yet are lost on restart, which only lets them be asked again.
+const SYNC_INTERVAL: usize = 16;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Record {
+    lang: String,
+    word: String,
+    at: u64,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub(crate) struct History {
+    // toml only accepts string keys, so channel ids are stored as decimal strings.
+    channels: BTreeMap<String, Vec<Record>>,
+    #[serde(skip)]
+    unsaved: usize,
+}
+
+lazy_static! {
+    pub(crate) static ref HISTORY: Arc<Mutex<History>> =
+        Arc::new(Mutex::new(settings::init_config(HISTORY_PATH).unwrap()));
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is before UNIX epoch")
+        .as_secs()
+}
+
+impl History {
+    pub(crate) fn record(&mut self, channel: u64, lang: Lang, word: &str) {
+        self.channels
+            .entry(channel.to_string())
+            .or_insert_with(Vec::new)
+            .push(Record {
+                lang: lang.as_code().to_string(),
+                word: word.to_string(),
+                at: now(),
+            });
+        self.unsaved += 1;
+    }
+
+    /// Forgets the words older than `RETENTION_DAYS` in every channel.
+    fn prune(&mut self) {
+        let now = now();
+        for records in self.channels.values_mut() {
+            records.retain(|record| record.at + RETENTION_DAYS * SECS_PER_DAY > now);
+        }
+        self.channels.retain(|_, records| !records.is_empty());
+    }
+
+    /// Words asked in `channel` within the last `days` days.
+    pub(crate) fn recent(&self, channel: u64, days: u32) -> IndexSet<(Lang, String)> {
+        let since = now().saturating_sub(u64::from(days) * SECS_PER_DAY);
+        self.channels
+            .get(&channel.to_string())
+            .map(|records| {
+                records
+                    .iter()
+                    .filter(|record| record.at >= since)
+                    // records of an unknown language, such as those edited by hand, are skipped.
+                    .filter_map(|record| {
+                        Lang::from_code(&record.lang).map(|lang| (lang, record.word.clone()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+pub(crate) fn record(channel: u64, lang: Lang, word: &str) -> Result<(), BotError> {
+    let history = &mut *HISTORY.lock().unwrap();
+    history.record(channel, lang, word);
+    if history.unsaved < SYNC_INTERVAL {
+        return Ok(());
+    }
+    history.prune();
+    history.unsaved = 0;
+    settings::sync_config(history, HISTORY_PATH)
+}
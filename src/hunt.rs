@@ -0,0 +1,195 @@
+use super::bot::{self, ContestData, Lang, Status};
+use super::history;
+use super::sort::Sorted;
+use crate::try_say;
+use indexmap::{IndexMap, IndexSet};
+use itertools::Itertools;
+use serenity::client::Context;
+use serenity::http::Http;
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Words shorter than this are not counted.
+const MIN_LENGTH: usize = 3;
+
+/// The letters are taken from a word of this many graphemes, since every subset of them is searched.
+const WORD_LENGTH: std::ops::RangeInclusive<usize> = 6..=9;
+
+/// Extra points for a word using all the letters.
+const FULL_BONUS: u32 = 5;
+
+pub const DEFAULT_SECS: u64 = 90;
+
+fn length(word: &str) -> usize {
+    UnicodeSegmentation::graphemes(word, true).count()
+}
+
+/// Players find as many words as possible made of some of the letters of `ans`.
+#[derive(Debug)]
+pub struct Hunt {
+    pub ans: String,
+    pub lang: Lang,
+    /// Every word made of the letters.
+    pub candidates: IndexSet<String>,
+    /// Words found by each player, with the name.
+    pub found: IndexMap<u64, (String, IndexSet<String>)>,
+    pub scores: IndexMap<String, ContestData>,
+    pub started: Instant,
+}
+
+impl Hunt {
+    fn points(&self, word: &str) -> u32 {
+        let len = length(word);
+        if len == length(&self.ans) {
+            len as u32 + FULL_BONUS
+        } else {
+            len as u32
+        }
+    }
+
+    fn report(&self) -> String {
+        let found = self
+            .found
+            .values()
+            .flat_map(|(_, words)| words.iter())
+            .collect::<IndexSet<_>>();
+        let full = self
+            .candidates
+            .iter()
+            .filter(|word| length(word) == length(&self.ans))
+            .join(", ");
+        format!(
+            "アナグラムハントが終了しました。\n文字: `{}` 全文字の単語: {}\n見つかった単語: {} / {}\n{}",
+            self.ans.sorted(),
+            full,
+            found.len(),
+            self.candidates.len(),
+            bot::aggregates(&self.scores, &[])
+        )
+    }
+}
+
+fn say(http: &Http, channel: ChannelId, response: String) {
+    if let Err(why) = channel.say(http, response) {
+        println!("{}", why);
+    }
+}
+
+/// Picks a word of `lang` and starts the hunt for `secs` seconds.
+pub(crate) fn start(
+    http: &Arc<Http>,
+    channel: ChannelId,
+    quiz: &mut Status,
+    lang: Lang,
+    secs: u64,
+) {
+    let dic = bot::get_dictionary(lang);
+    let rng = &mut rand::thread_rng();
+    let ans = dic
+        .get_except(rng, |word| !WORD_LENGTH.contains(&length(word)))
+        .unwrap_or_else(|| dic.get(rng))
+        .to_string();
+    if let Err(why) = history::record(*channel.as_u64(), lang, &ans) {
+        println!("{}", why);
+    }
+    let candidates = dic
+        .sub_anagrams(&ans, MIN_LENGTH)
+        .into_iter()
+        .collect::<IndexSet<_>>();
+    say(
+        http,
+        channel,
+        format!(
+            "アナグラムハント！ 次の文字の一部を並べ替えてできる {symbol} をできるだけ多く答えてください。\n`{prob}`\n{min} 文字以上、制限時間 {secs} 秒、長い単語ほど高得点で全文字を使うと {bonus} 点のボーナスです。 (全 {count} 語)",
+            symbol = lang.as_symbol(),
+            prob = ans.sorted(),
+            min = MIN_LENGTH,
+            secs = secs,
+            bonus = FULL_BONUS,
+            count = candidates.len(),
+        ),
+    );
+    let started = Instant::now();
+    *quiz = Status::Hunting(Hunt {
+        ans,
+        lang,
+        candidates,
+        found: IndexMap::new(),
+        scores: IndexMap::new(),
+        started,
+    });
+    let http = http.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_secs(secs));
+        let quiz = &mut *bot::QUIZ.lock().unwrap();
+        match quiz {
+            Status::Hunting(hunt) if hunt.started == started => {
+                say(&http, channel, format!("時間切れです！\n{}", hunt.report()));
+                *quiz = Status::StandingBy;
+            }
+            _ => {}
+        }
+    });
+}
+
+/// Counts the words of the message which the author has not found yet.
+/// The message is deleted so that the words are not revealed to others.
+pub(crate) fn answer_check(ctx: &mut Context, msg: &Message, quiz: &mut Status) {
+    let hunt = match quiz {
+        Status::Hunting(hunt) => hunt,
+        _ => return,
+    };
+    let elapsed = hunt.started.elapsed().as_secs_f32();
+    let dic = bot::get_dictionary(hunt.lang);
+    let words = msg
+        .content
+        .split_whitespace()
+        .map(|word| dic.normalize(word))
+        .filter(|word| hunt.candidates.contains(word))
+        .collect::<Vec<_>>();
+    if words.is_empty() {
+        return;
+    }
+    let (_, found) = hunt
+        .found
+        .entry(*msg.author.id.as_u64())
+        .or_insert_with(|| (msg.author.name.clone(), IndexSet::new()));
+    let new = words
+        .into_iter()
+        .filter(|word| found.insert(word.clone()))
+        .collect::<Vec<_>>();
+    let points = new.iter().map(|word| hunt.points(word)).collect::<Vec<_>>();
+    let data = hunt
+        .scores
+        .entry(msg.author.name.clone())
+        .or_insert(ContestData::default());
+    for point in points.iter() {
+        data.add_word(elapsed, *point);
+    }
+    if let Err(why) = msg.channel_id.delete_message(&ctx, msg.id) {
+        println!("{}", why);
+    }
+    if !new.is_empty() {
+        try_say!(
+            ctx,
+            msg,
+            format!(
+                "{} さん +{} 点 ({})",
+                &msg.author.name,
+                points.iter().sum::<u32>(),
+                data.as_string()
+            )
+        );
+    }
+}
+
+/// Ends the hunt at once and shows the result.
+pub(crate) fn giveup(ctx: &mut Context, msg: &Message, quiz: &mut Status) {
+    if let Status::Hunting(hunt) = std::mem::replace(quiz, Status::StandingBy) {
+        try_say!(ctx, msg, hunt.report());
+    }
+}
@@ -0,0 +1,640 @@
+use super::dictionary::{Entry, RawDictionary};
+use super::error::BotError;
+use super::normalize::Form;
+use clap::{App, AppSettings, Arg};
+use encoding_rs::{Encoding, UTF_8};
+use indexmap::{IndexMap, IndexSet};
+use quick_error::ResultExt;
+use regex::Regex;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Extensions of the formats which can be read, in the order `Dictionary::load` looks for them.
+pub const EXTENSIONS: [&str; 6] = ["toml", "txt", "csv", "tsv", "json", "dic"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Toml,
+    /// One word per line, optionally followed by its frequency.
+    Text,
+    /// With a header naming the columns, such as `word,meaning,tags,frequency`.
+    Csv,
+    Tsv,
+    /// An array of words or of entries, or an object in the same shape as the toml.
+    Json,
+    /// A Hunspell `.dic` with the `.aff` of the same name.
+    Hunspell,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "toml" => Some(Format::Toml),
+            "txt" | "lst" => Some(Format::Text),
+            "csv" => Some(Format::Csv),
+            "tsv" => Some(Format::Tsv),
+            "json" => Some(Format::Json),
+            "dic" => Some(Format::Hunspell),
+            _ => None,
+        }
+    }
+}
+
+/// Which words are kept. Words without a frequency pass the frequency cutoff.
+#[derive(Debug, Default, Clone)]
+pub struct Filter {
+    pub min_len: Option<usize>,
+    pub max_len: Option<usize>,
+    pub min_freq: Option<u64>,
+    /// Only this many of the most frequent words are kept.
+    pub top: Option<usize>,
+}
+
+impl Filter {
+    fn accepts(&self, word: &str, freq: Option<u64>) -> bool {
+        let len = UnicodeSegmentation::graphemes(word, true).count();
+        self.min_len.map_or(true, |min| len >= min)
+            && self.max_len.map_or(true, |max| len <= max)
+            && self
+                .min_freq
+                .map_or(true, |min| freq.map_or(true, |freq| freq >= min))
+    }
+
+    fn apply(&self, mut records: Vec<Record>) -> Vec<Record> {
+        records.retain(|record| self.accepts(&record.entry.word, record.freq));
+        if let Some(top) = self.top {
+            // lists without frequencies are taken as already sorted by it.
+            records.sort_by_key(|record| std::cmp::Reverse(record.freq));
+            records.truncate(top);
+        }
+        records
+    }
+}
+
+/// A word read from a list, with what the list says about it.
+#[derive(Debug)]
+struct Record {
+    entry: Entry,
+    freq: Option<u64>,
+}
+
+impl Record {
+    fn new(word: &str, freq: Option<u64>) -> Record {
+        Record {
+            entry: Entry {
+                word: word.to_string(),
+                ..Entry::default()
+            },
+            freq,
+        }
+    }
+
+    fn has_details(&self) -> bool {
+        let entry = &self.entry;
+        entry.meaning.is_some()
+            || entry.reading.is_some()
+            || entry.pos.is_some()
+            || !entry.tags.is_empty()
+            || entry.source.is_some()
+    }
+}
+
+/// Words with details become entries, and the others plain questions.
+fn into_raw(records: Vec<Record>, full: Option<Vec<String>>) -> RawDictionary {
+    let (entries, plain): (Vec<_>, Vec<_>) = records.into_iter().partition(Record::has_details);
+    RawDictionary {
+        questions: plain.into_iter().map(|record| record.entry.word).collect(),
+        full,
+        entries: entries.into_iter().map(|record| record.entry).collect(),
+        ..RawDictionary::default()
+    }
+}
+
+/// Filters a list already in the dictionary format, keeping how it is normalized.
+fn filter_raw(raw: RawDictionary, filter: &Filter) -> RawDictionary {
+    let full = raw.full.map(|full| {
+        full.into_iter()
+            .filter(|word| filter.accepts(word, None))
+            .collect()
+    });
+    let records = raw
+        .questions
+        .iter()
+        .map(|word| Record::new(word, None))
+        .chain(
+            raw.entries
+                .into_iter()
+                .map(|entry| Record { entry, freq: None }),
+        )
+        .collect();
+    RawDictionary {
+        normalization: raw.normalization,
+        case_fold: raw.case_fold,
+        ..into_raw(filter.apply(records), full)
+    }
+}
+
+fn decode(path: &Path, encoding: &'static Encoding) -> Result<String, BotError> {
+    encoding
+        .decode_without_bom_handling_and_without_replacement(&fs::read(path).context(path)?)
+        .map(|text| text.into_owned())
+        .ok_or_else(|| BotError::InvalidEncoding(path.to_string_lossy().to_string()))
+}
+
+fn read_to_string(path: &Path) -> Result<String, BotError> {
+    decode(path, UTF_8)
+}
+
+/// The encoding of a Hunspell `.dic` and `.aff` named by `SET` of the `.aff`, or UTF-8 if not given.
+fn hunspell_encoding(aff: &Path) -> Result<&'static Encoding, BotError> {
+    // `SET` is ASCII in any encoding.
+    let name = String::from_utf8_lossy(&fs::read(aff).context(aff)?)
+        .lines()
+        .find_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            match fields.as_slice() {
+                ["SET", name] => Some(name.to_string()),
+                _ => None,
+            }
+        });
+    match name {
+        None => Ok(UTF_8),
+        // Hunspell calls the Windows code pages such as `microsoft-cp1251`.
+        Some(name) => Encoding::for_label(name.trim_start_matches("microsoft-").as_bytes())
+            .ok_or(BotError::UnknownEncoding(name)),
+    }
+}
+
+/// Reads a word list of any format into the dictionary format.
+pub(crate) fn read(path: &Path, filter: &Filter) -> Result<RawDictionary, BotError> {
+    let format = Format::from_path(path)
+        .ok_or_else(|| BotError::UnknownFormat(path.to_string_lossy().to_string()))?;
+    match format {
+        Format::Toml => {
+            let raw = toml::from_slice(&fs::read(path).context(path)?)?;
+            Ok(filter_raw(raw, filter))
+        }
+        Format::Text => Ok(into_raw(
+            filter.apply(read_text(&read_to_string(path)?)),
+            None,
+        )),
+        Format::Csv => Ok(into_raw(filter.apply(read_table(path, b',')?), None)),
+        Format::Tsv => Ok(into_raw(filter.apply(read_table(path, b'\t')?), None)),
+        Format::Json => read_json(&read_to_string(path)?, filter),
+        Format::Hunspell => {
+            let aff = path.with_extension("aff");
+            let (rules, encoding) = if aff.exists() {
+                let encoding = hunspell_encoding(&aff)?;
+                (Affixes::parse(&decode(&aff, encoding)?), encoding)
+            } else {
+                (Affixes::default(), UTF_8)
+            };
+            let (stems, expanded) = rules.expand(&decode(path, encoding)?);
+            let full = expanded
+                .into_iter()
+                .filter(|word| filter.accepts(word, None))
+                .collect();
+            let stems = stems.iter().map(|word| Record::new(word, None)).collect();
+            Ok(into_raw(filter.apply(stems), Some(full)))
+        }
+    }
+}
+
+/// A line of `<word>` or `<word> <frequency>`. Words may contain spaces,
+/// so the frequency is split off by a tab, or else by the last space only if it is a number.
+fn text_record(line: &str) -> Record {
+    if let [word, freq, ..] = line.split('\t').collect::<Vec<_>>().as_slice() {
+        return Record::new(word.trim(), freq.trim().parse().ok());
+    }
+    match line.rsplitn(2, ' ').collect::<Vec<_>>().as_slice() {
+        [freq, word] if freq.parse::<u64>().is_ok() => {
+            Record::new(word.trim_end(), freq.parse().ok())
+        }
+        _ => Record::new(line, None),
+    }
+}
+
+/// Lines of `text_record`, where `#` starts a comment line.
+fn read_text(text: &str) -> Vec<Record> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(text_record)
+        .collect()
+}
+
+/// Tags are separated by `;` or `|` in a column.
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(|c| c == ';' || c == '|')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// A table whose header names the columns. Without a `word` column the first one is the word.
+fn read_table(path: &Path, delimiter: u8) -> Result<Vec<Record>, BotError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)?;
+    let headers = reader
+        .headers()?
+        .iter()
+        .map(|header| header.trim().to_lowercase())
+        .collect::<Vec<_>>();
+    let column = |names: &[&str]| {
+        headers
+            .iter()
+            .position(|header| names.contains(&header.as_str()))
+    };
+    let word = column(&["word", "lemma", "headword"]).unwrap_or(0);
+    let meaning = column(&["meaning", "translation", "definition", "gloss"]);
+    let reading = column(&["reading", "kana", "yomi"]);
+    let pos = column(&["pos", "part_of_speech"]);
+    let tags = column(&["tags", "tag", "category"]);
+    let source = column(&["source", "license", "licence"]);
+    let freq = column(&["frequency", "freq", "count"]);
+    let mut records = Vec::new();
+    for row in reader.records() {
+        let row = row?;
+        let get = |index: Option<usize>| {
+            index
+                .and_then(|index| row.get(index))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let word = match get(Some(word)) {
+            Some(word) => word,
+            None => continue,
+        };
+        records.push(Record {
+            entry: Entry {
+                word,
+                meaning: get(meaning),
+                reading: get(reading),
+                pos: get(pos),
+                tags: get(tags).map_or_else(Vec::new, |tags| split_tags(&tags)),
+                source: get(source),
+            },
+            freq: get(freq).and_then(|freq| freq.parse().ok()),
+        });
+    }
+    Ok(records)
+}
+
+fn read_json(text: &str, filter: &Filter) -> Result<RawDictionary, BotError> {
+    use serde_json::Value;
+    let records = match serde_json::from_str::<Value>(text)? {
+        Value::Array(items) => items
+            .into_iter()
+            .filter_map(|item| match item {
+                Value::String(word) => Some(Record::new(&word, None)),
+                Value::Object(_) => {
+                    let freq = item.get("frequency").and_then(Value::as_u64);
+                    serde_json::from_value::<Entry>(item)
+                        .ok()
+                        .map(|entry| Record { entry, freq })
+                }
+                _ => None,
+            })
+            .collect(),
+        object => {
+            return Ok(filter_raw(
+                serde_json::from_value::<RawDictionary>(object)?,
+                filter,
+            ));
+        }
+    };
+    Ok(into_raw(filter.apply(records), None))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FlagType {
+    Char,
+    Long,
+    Num,
+}
+
+impl Default for FlagType {
+    fn default() -> Self {
+        FlagType::Char
+    }
+}
+
+#[derive(Debug)]
+struct Affix {
+    strip: String,
+    add: String,
+    condition: Regex,
+}
+
+#[derive(Debug, Default)]
+struct AffixClass {
+    cross: bool,
+    rules: Vec<Affix>,
+}
+
+/// Prefix and suffix rules of a Hunspell `.aff`. Only the rules needed to expand words are read.
+#[derive(Debug, Default)]
+struct Affixes {
+    flag: FlagType,
+    prefixes: IndexMap<String, AffixClass>,
+    suffixes: IndexMap<String, AffixClass>,
+}
+
+/// Turns the condition of a rule into a regex anchored at the start or the end of a word.
+/// Conditions are already regexes of `.`, `[...]` and `[^...]`, so only the rest is escaped.
+/// A malformed condition is `None`, and its rule is skipped.
+fn condition(condition: &str, suffix: bool) -> Option<Regex> {
+    let mut pattern = String::new();
+    let mut in_class = false;
+    for c in condition.chars() {
+        match c {
+            '[' => in_class = true,
+            ']' => in_class = false,
+            _ => {}
+        }
+        if in_class || "[].^".contains(c) {
+            pattern.push(c);
+        } else {
+            pattern += &regex::escape(&c.to_string());
+        }
+    }
+    let pattern = if suffix {
+        format!("(?:{})$", pattern)
+    } else {
+        format!("^(?:{})", pattern)
+    };
+    Regex::new(&pattern).ok()
+}
+
+/// `0` means an empty string in `.aff`.
+fn affix_string(field: &str) -> String {
+    let field = field.split('/').next().unwrap_or_default();
+    if field == "0" {
+        String::new()
+    } else {
+        field.to_string()
+    }
+}
+
+impl Affixes {
+    fn parse(aff: &str) -> Affixes {
+        let mut affixes = Affixes::default();
+        for line in aff.lines() {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            match fields.as_slice() {
+                ["FLAG", "long"] => affixes.flag = FlagType::Long,
+                ["FLAG", "num"] => affixes.flag = FlagType::Num,
+                [kind @ "PFX", flag, cross, count] | [kind @ "SFX", flag, cross, count]
+                    if count.parse::<usize>().is_ok() =>
+                {
+                    let classes = if *kind == "PFX" {
+                        &mut affixes.prefixes
+                    } else {
+                        &mut affixes.suffixes
+                    };
+                    classes.entry(flag.to_string()).or_default().cross = *cross == "Y";
+                }
+                [kind @ "PFX", flag, strip, add, cond, ..]
+                | [kind @ "SFX", flag, strip, add, cond, ..] => {
+                    let suffix = *kind == "SFX";
+                    let condition = match condition(cond, suffix) {
+                        Some(condition) => condition,
+                        None => continue,
+                    };
+                    let classes = if suffix {
+                        &mut affixes.suffixes
+                    } else {
+                        &mut affixes.prefixes
+                    };
+                    classes
+                        .entry(flag.to_string())
+                        .or_default()
+                        .rules
+                        .push(Affix {
+                            strip: affix_string(strip),
+                            add: affix_string(add),
+                            condition,
+                        });
+                }
+                _ => {}
+            }
+        }
+        affixes
+    }
+
+    fn flags(&self, flags: &str) -> Vec<String> {
+        match self.flag {
+            FlagType::Char => flags.chars().map(|c| c.to_string()).collect(),
+            FlagType::Long => flags
+                .chars()
+                .collect::<Vec<_>>()
+                .chunks(2)
+                .map(|chunk| chunk.iter().collect())
+                .collect(),
+            FlagType::Num => flags.split(',').map(str::to_string).collect(),
+        }
+    }
+
+    fn apply_suffix(word: &str, rule: &Affix) -> Option<String> {
+        if rule.condition.is_match(word) && word.ends_with(&rule.strip) {
+            Some(word[..word.len() - rule.strip.len()].to_string() + &rule.add)
+        } else {
+            None
+        }
+    }
+
+    fn apply_prefix(word: &str, rule: &Affix) -> Option<String> {
+        if rule.condition.is_match(word) && word.starts_with(&rule.strip) {
+            Some(rule.add.clone() + &word[rule.strip.len()..])
+        } else {
+            None
+        }
+    }
+
+    /// The stems of `.dic` and every form made by their affixes, stems included.
+    fn expand(&self, dic: &str) -> (Vec<String>, Vec<String>) {
+        let mut stems = Vec::new();
+        let mut forms = IndexSet::new();
+        // the first line is the number of words.
+        for line in dic.lines().skip(1) {
+            let line = line
+                .split(|c: char| c == '\t' || c == ' ')
+                .next()
+                .unwrap_or_default();
+            let mut iter = line.splitn(2, '/');
+            let stem = iter.next().unwrap_or_default();
+            if stem.is_empty() {
+                continue;
+            }
+            stems.push(stem.to_string());
+            forms.insert(stem.to_string());
+            let flags = iter.next().map_or_else(Vec::new, |flags| self.flags(flags));
+            let prefixes = flags
+                .iter()
+                .filter_map(|flag| self.prefixes.get(flag))
+                .collect::<Vec<_>>();
+            for class in prefixes.iter() {
+                for rule in class.rules.iter() {
+                    forms.extend(Affixes::apply_prefix(stem, rule));
+                }
+            }
+            for class in flags.iter().filter_map(|flag| self.suffixes.get(flag)) {
+                for rule in class.rules.iter() {
+                    let form = match Affixes::apply_suffix(stem, rule) {
+                        Some(form) => form,
+                        None => continue,
+                    };
+                    for prefix in prefixes.iter().filter(|prefix| prefix.cross && class.cross) {
+                        for rule in prefix.rules.iter() {
+                            forms.extend(Affixes::apply_prefix(&form, rule));
+                        }
+                    }
+                    forms.insert(form);
+                }
+            }
+        }
+        (stems, forms.into_iter().collect())
+    }
+}
+
+fn number<T: std::str::FromStr>(matches: &clap::ArgMatches, name: &str) -> Option<T> {
+    matches.value_of(name).and_then(|value| value.parse().ok())
+}
+
+/// `import` subcommand of the binary: converts word lists into the toml of `Dictionary::load`.
+pub(crate) fn run<I: Iterator<Item = String>>(args: I) -> Result<(), BotError> {
+    let matches = App::new("import")
+        .setting(AppSettings::ColorNever)
+        .about("Converts word lists (txt, csv, tsv, json, Hunspell dic/aff) into a dictionary toml")
+        .arg(
+            Arg::with_name("questions")
+                .long("questions")
+                .takes_value(true)
+                .multiple(true)
+                .help("Word lists of the words asked"),
+        )
+        .arg(
+            Arg::with_name("full")
+                .long("full")
+                .takes_value(true)
+                .multiple(true)
+                .help("Word lists of the words only accepted as answers"),
+        )
+        .arg(
+            Arg::with_name("min-len")
+                .long("min-len")
+                .takes_value(true)
+                .help("Drops words with fewer graphemes"),
+        )
+        .arg(
+            Arg::with_name("max-len")
+                .long("max-len")
+                .takes_value(true)
+                .help("Drops words with more graphemes"),
+        )
+        .arg(
+            Arg::with_name("min-freq")
+                .long("min-freq")
+                .takes_value(true)
+                .help("Drops words less frequent than this"),
+        )
+        .arg(
+            Arg::with_name("top")
+                .long("top")
+                .takes_value(true)
+                .help("Keeps only this many of the most frequent words of each list"),
+        )
+        .arg(
+            Arg::with_name("normalization")
+                .long("normalization")
+                .takes_value(true)
+                .possible_values(&["nfc", "nfkc"])
+                .help("Unicode normal form the words are compared in, NFKC if not given"),
+        )
+        .arg(
+            Arg::with_name("no-case-fold")
+                .long("no-case-fold")
+                .help("Compares the words without lowercasing them"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .help("Output toml, or the standard output if not given"),
+        )
+        .get_matches_from(args);
+    let filter = Filter {
+        min_len: number(&matches, "min-len"),
+        max_len: number(&matches, "max-len"),
+        min_freq: number(&matches, "min-freq"),
+        top: number(&matches, "top"),
+    };
+    let mut questions = IndexSet::new();
+    let mut entries = IndexMap::new();
+    let mut full = IndexSet::new();
+    for path in matches.values_of("questions").into_iter().flatten() {
+        let raw = read(Path::new(path), &filter)?;
+        questions.extend(raw.questions);
+        full.extend(raw.full.into_iter().flatten());
+        for entry in raw.entries {
+            entries.insert(entry.word.clone(), entry);
+        }
+    }
+    for path in matches.values_of("full").into_iter().flatten() {
+        let raw = read(Path::new(path), &filter)?;
+        full.extend(raw.questions);
+        full.extend(raw.full.into_iter().flatten());
+        full.extend(raw.entries.into_iter().map(|entry| entry.word));
+    }
+    let raw = RawDictionary {
+        questions: questions
+            .into_iter()
+            .filter(|word| !entries.contains_key(word))
+            .collect(),
+        full: if full.is_empty() {
+            None
+        } else {
+            Some(full.into_iter().collect())
+        },
+        normalization: matches
+            .value_of("normalization")
+            .and_then(|form| match form {
+                "nfc" => Some(Form::Nfc),
+                "nfkc" => Some(Form::Nfkc),
+                _ => None,
+            }),
+        case_fold: if matches.is_present("no-case-fold") {
+            Some(false)
+        } else {
+            None
+        },
+        entries: entries.into_iter().map(|(_, entry)| entry).collect(),
+    };
+    if raw.questions.is_empty() && raw.entries.is_empty() {
+        return Err(BotError::NoQuestions);
+    }
+    let buffer = toml::to_string(&raw).context("dictionary")?;
+    match matches.value_of("output") {
+        Some(output) => {
+            let path = Path::new(output);
+            File::create(path)
+                .and_then(|mut f| f.write_all(buffer.as_bytes()))
+                .context(path)?;
+        }
+        None => print!("{}", buffer),
+    }
+    eprintln!(
+        "questions: {}, entries: {}, full: {}",
+        raw.questions.len(),
+        raw.entries.len(),
+        raw.full.as_ref().map_or(0, Vec::len)
+    );
+    Ok(())
+}
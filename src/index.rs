@@ -0,0 +1,317 @@
+use super::dictionary::{Dictionary, Entry, NAMES};
+use super::error::BotError;
+use super::normalize::Normalization;
+use super::sort::Sorted;
+use clap::{App, AppSettings, Arg};
+use fst::{IntoStreamer, Map, Set, Streamer};
+use indexmap::IndexSet;
+use memmap::Mmap;
+use quick_error::ResultExt;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryInto;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Extension of compiled dictionaries.
+pub const EXTENSION: &str = "idx";
+
+const MAGIC: &[u8; 4] = b"NZIX";
+
+const VERSION: u32 = 3;
+
+/// Number of the sections following the version.
+const SECTIONS: usize = 7;
+
+/// Separates the sorted graphemes from the word in the keys of the anagram table.
+const SEPARATOR: u8 = 0;
+
+/// A part of the memory-mapped file.
+#[derive(Clone)]
+pub(crate) struct Section {
+    map: Arc<Mmap>,
+    start: usize,
+    end: usize,
+}
+
+impl AsRef<[u8]> for Section {
+    fn as_ref(&self) -> &[u8] {
+        &self.map[self.start..self.end]
+    }
+}
+
+/// Flags of the dictionary, stored as toml at the end of the file.
+#[derive(Default, Serialize, Deserialize)]
+struct Header {
+    has_full: bool,
+    /// How the words were normalized, which guesses have to be normalized in.
+    /// It is a table, so it comes after the others.
+    normalization: Normalization,
+}
+
+/// Entries are stored as toml in their own section, which needs a table at the top.
+#[derive(Default, Serialize, Deserialize)]
+struct Entries {
+    #[serde(default)]
+    entries: Vec<Entry>,
+}
+
+/// A dictionary compiled by `compile`.
+/// Words only accepted as answers and the anagram table stay on the disk, and are paged in when looked up.
+///
+/// The file is `NZIX`, the version and seven sections, each preceded by its length:
+/// the set of `full`, the set of `<sorted graphemes>\0<word>` of every word,
+/// the map from the questions to their positions, the offsets and the bytes of the questions,
+/// the entries and the `Header`.
+pub(crate) struct Index {
+    full: Set<Section>,
+    anagrams: Set<Section>,
+    header: Header,
+}
+
+impl std::fmt::Debug for Index {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Index")
+            .field("full", &self.full.len())
+            .field("anagrams", &self.anagrams.len())
+            .finish()
+    }
+}
+
+/// Questions of a compiled dictionary in their order, read from the map instead of copied.
+pub(crate) struct Questions {
+    /// Positions of the questions by the words.
+    positions: Map<Section>,
+    /// `len + 1` offsets into `words`, each a little-endian `u64`.
+    offsets: Section,
+    /// The questions concatenated in UTF-8.
+    words: Section,
+}
+
+impl std::fmt::Debug for Questions {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Questions")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl Questions {
+    pub(crate) fn len(&self) -> usize {
+        self.offsets.as_ref().len() / 8 - 1
+    }
+
+    fn offset(&self, index: usize) -> usize {
+        let bytes = &self.offsets.as_ref()[index * 8..index * 8 + 8];
+        u64::from_le_bytes(bytes.try_into().unwrap()) as usize
+    }
+
+    pub(crate) fn get(&self, index: usize) -> Option<&str> {
+        if index >= self.len() {
+            return None;
+        }
+        let bytes = self
+            .words
+            .as_ref()
+            .get(self.offset(index)..self.offset(index + 1))?;
+        std::str::from_utf8(bytes).ok()
+    }
+
+    pub(crate) fn position(&self, word: &str) -> Option<usize> {
+        self.positions.get(word).map(|index| index as usize)
+    }
+
+    /// Whether the offsets are increasing within the words, each of which is UTF-8.
+    fn is_valid(&self) -> bool {
+        let offsets = self.offsets.as_ref();
+        if offsets.len() < 8 || offsets.len() % 8 != 0 {
+            return false;
+        }
+        (0..self.len()).all(|index| {
+            let (start, end) = (self.offset(index), self.offset(index + 1));
+            start <= end
+                && self
+                    .words
+                    .as_ref()
+                    .get(start..end)
+                    .map_or(false, |bytes| std::str::from_utf8(bytes).is_ok())
+        }) && self.offset(self.len()) == self.words.as_ref().len()
+    }
+}
+
+fn anagram_key(sorted: &str) -> Vec<u8> {
+    let mut key = sorted.as_bytes().to_vec();
+    key.push(SEPARATOR);
+    key
+}
+
+fn write_section<W: Write>(out: &mut W, bytes: &[u8]) -> std::io::Result<()> {
+    out.write_all(&(bytes.len() as u64).to_le_bytes())?;
+    out.write_all(bytes)
+}
+
+/// Writes the index of the words, which must be normalized as `Dictionary` does.
+/// It is written beside `path` and renamed over it, since a running bot may have the old one mapped.
+pub(crate) fn write<'a, I: Iterator<Item = &'a str> + Clone>(
+    path: &Path,
+    questions: I,
+    full: Option<&'a IndexSet<String>>,
+    normalization: Normalization,
+    entries: Vec<Entry>,
+) -> Result<(), BotError> {
+    let anagrams = Set::from_iter(
+        questions
+            .clone()
+            .chain(full.into_iter().flatten().map(String::as_str))
+            .map(|word| {
+                [
+                    anagram_key(&word.to_string().sorted()),
+                    word.as_bytes().to_vec(),
+                ]
+                .concat()
+            })
+            .collect::<BTreeSet<_>>(),
+    )?;
+    let positions = Map::from_iter(
+        questions
+            .clone()
+            .enumerate()
+            .map(|(index, word)| (word, index as u64))
+            .collect::<BTreeMap<_, _>>(),
+    )?;
+    let mut offsets = 0u64.to_le_bytes().to_vec();
+    let mut words = Vec::new();
+    for word in questions {
+        words.extend_from_slice(word.as_bytes());
+        offsets.extend_from_slice(&(words.len() as u64).to_le_bytes());
+    }
+    let header = Header {
+        has_full: full.is_some(),
+        normalization,
+    };
+    let full = Set::from_iter(full.into_iter().flatten().collect::<BTreeSet<_>>())?;
+    let entries = toml::to_string(&Entries { entries }).context("index")?;
+    let header = toml::to_string(&header).context("index")?;
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    let temporary = Path::new(&temporary);
+    let mut f = File::create(temporary).context(temporary)?;
+    f.write_all(MAGIC)
+        .and_then(|_| f.write_all(&VERSION.to_le_bytes()))
+        .and_then(|_| write_section(&mut f, full.as_fst().as_bytes()))
+        .and_then(|_| write_section(&mut f, anagrams.as_fst().as_bytes()))
+        .and_then(|_| write_section(&mut f, positions.as_fst().as_bytes()))
+        .and_then(|_| write_section(&mut f, &offsets))
+        .and_then(|_| write_section(&mut f, &words))
+        .and_then(|_| write_section(&mut f, entries.as_bytes()))
+        .and_then(|_| write_section(&mut f, header.as_bytes()))
+        .and_then(|_| f.sync_all())
+        .context(temporary)?;
+    fs::rename(temporary, path).context(path)?;
+    Ok(())
+}
+
+impl Index {
+    /// Opens the index with its questions, and the entries which are parsed into memory.
+    pub(crate) fn open(path: &Path) -> Result<(Index, Questions, Vec<Entry>), BotError> {
+        let file = File::open(path).context(path)?;
+        // `write` replaces the file by renaming instead of writing into it, so the map stays valid.
+        let map = Arc::new(unsafe { Mmap::map(&file) }.context(path)?);
+        let invalid = || BotError::InvalidIndex(path.to_string_lossy().to_string());
+        if map.len() < 8 || &map[..4] != MAGIC || map[4..8] != VERSION.to_le_bytes() {
+            return Err(invalid());
+        }
+        let mut sections = Vec::new();
+        let mut start = 8;
+        for _ in 0..SECTIONS {
+            let len = map
+                .get(start..start + 8)
+                .ok_or_else(invalid)?
+                .try_into()
+                .map(u64::from_le_bytes)
+                .map_err(|_| invalid())? as usize;
+            start += 8;
+            if start + len > map.len() {
+                return Err(invalid());
+            }
+            sections.push(Section {
+                map: Arc::clone(&map),
+                start,
+                end: start + len,
+            });
+            start += len;
+        }
+        let mut sections = sections.into_iter();
+        let mut next = || sections.next().unwrap();
+        let full = Set::new(next())?;
+        let anagrams = Set::new(next())?;
+        let questions = Questions {
+            positions: Map::new(next())?,
+            offsets: next(),
+            words: next(),
+        };
+        if !questions.is_valid() {
+            return Err(invalid());
+        }
+        let entries: Entries = toml::from_slice(next().as_ref())?;
+        let header = toml::from_slice(next().as_ref())?;
+        let index = Index {
+            full,
+            anagrams,
+            header,
+        };
+        Ok((index, questions, entries.entries))
+    }
+
+    pub(crate) fn normalization(&self) -> Normalization {
+        self.header.normalization
+    }
+
+    pub(crate) fn contains_full(&self, word: &str) -> bool {
+        self.full.contains(word)
+    }
+
+    pub(crate) fn full_len(&self) -> Option<usize> {
+        if self.header.has_full {
+            Some(self.full.len())
+        } else {
+            None
+        }
+    }
+
+    /// Words consisting of the same graphemes as `sorted`, which must be sorted.
+    pub(crate) fn anagrams(&self, sorted: &str) -> Vec<String> {
+        let start = anagram_key(sorted);
+        let mut end = start.clone();
+        *end.last_mut().unwrap() = SEPARATOR + 1;
+        let mut stream = self.anagrams.range().ge(&start).lt(&end).into_stream();
+        let mut words = Vec::new();
+        while let Some(key) = stream.next() {
+            words.push(String::from_utf8_lossy(&key[start.len()..]).into_owned());
+        }
+        words
+    }
+}
+
+/// `compile` subcommand of the binary: compiles the dictionaries of `DIC_DIR` named, or all of them.
+pub(crate) fn run<I: Iterator<Item = String>>(args: I) -> Result<(), BotError> {
+    let matches = App::new("compile")
+        .setting(AppSettings::ColorNever)
+        .about("Compiles dictionaries of DIC_DIR into memory-mapped indices loaded at startup")
+        .arg(
+            Arg::with_name("names")
+                .multiple(true)
+                .help("Names of the dictionaries, such as `english`, or all of them if not given"),
+        )
+        .get_matches_from(args);
+    let names = matches
+        .values_of("names")
+        .map_or_else(|| NAMES.to_vec(), |names| names.collect());
+    for name in names {
+        let path = Dictionary::compile(name)?;
+        println!("{} => {}", name, path.display());
+    }
+    Ok(())
+}
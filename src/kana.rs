@@ -0,0 +1,57 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+const SMALL_KANA: [(char, char); 12] = [
+    ('ぁ', 'あ'),
+    ('ぃ', 'い'),
+    ('ぅ', 'う'),
+    ('ぇ', 'え'),
+    ('ぉ', 'お'),
+    ('っ', 'つ'),
+    ('ゃ', 'や'),
+    ('ゅ', 'ゆ'),
+    ('ょ', 'よ'),
+    ('ゎ', 'わ'),
+    ('ゕ', 'か'),
+    ('ゖ', 'け'),
+];
+
+const LONG_VOWEL: &str = "ー";
+
+fn to_hiragana(c: char) -> char {
+    if ('ァ'..='ヶ').contains(&c) {
+        std::char::from_u32(c as u32 - 0x60).unwrap_or(c)
+    } else {
+        c
+    }
+}
+
+/// Normalizes a grapheme for shiritori: katakana to hiragana, small kana to large ones,
+/// and letters to lowercase.
+pub fn normalize(grapheme: &str) -> String {
+    grapheme
+        .chars()
+        .map(to_hiragana)
+        .map(|c| {
+            SMALL_KANA
+                .iter()
+                .find(|(small, _)| *small == c)
+                .map_or(c, |(_, large)| *large)
+        })
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// The normalized first grapheme of `word`.
+pub fn head(word: &str) -> Option<String> {
+    UnicodeSegmentation::graphemes(word, true)
+        .next()
+        .map(normalize)
+}
+
+/// The normalized last grapheme of `word`, where the long vowel mark is skipped.
+pub fn tail(word: &str) -> Option<String> {
+    UnicodeSegmentation::graphemes(word, true)
+        .rev()
+        .find(|grapheme| *grapheme != LONG_VOWEL)
+        .map(normalize)
+}
@@ -0,0 +1,336 @@
+use super::bot::{self, Lang, Status};
+use super::kana;
+use crate::try_say;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use rand::distributions::{Distribution, WeightedIndex};
+use serenity::client::Context;
+use serenity::http::Http;
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use unicode_segmentation::UnicodeSegmentation;
+
+pub const LETTER_COUNT: usize = 9;
+
+const MIN_VOWELS: usize = 3;
+
+const MIN_CONSONANTS: usize = 4;
+
+const TIME_LIMIT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Vowel,
+    Consonant,
+}
+
+/// Letters of `lang` separated by spaces, and their weights of drawing.
+fn table(lang: Lang, kind: Kind) -> (&'static str, &'static [u32]) {
+    match (lang, kind) {
+        (Lang::En, Kind::Vowel) => ("a e i o u", &[15, 21, 13, 13, 5]),
+        (Lang::En, Kind::Consonant) => (
+            "b c d f g h j k l m n p q r s t v w x y z",
+            &[2, 3, 6, 2, 3, 2, 1, 1, 5, 4, 8, 4, 1, 9, 9, 9, 1, 1, 1, 1, 1],
+        ),
+        (Lang::Ja, Kind::Vowel) => ("あ い う え お", &[4, 5, 4, 2, 3]),
+        (Lang::Ja, Kind::Consonant) => (
+            "か き く け こ さ し す せ そ た ち つ て と な に ぬ ね の は ひ ふ へ ほ ま み む め も や ゆ よ ら り る れ ろ わ ん が ぎ ぐ げ ご ざ じ ず ぜ ぞ だ で ど ば び ぶ べ ぼ ぱ ぴ ぷ ぺ ぽ ー",
+            &[
+                3, 3, 3, 2, 3, 2, 4, 2, 2, 2, 3, 2, 2, 2, 3, 2, 2, 1, 1, 2, 2, 1, 1, 1, 1, 2, 1, 1,
+                1, 1, 1, 1, 2, 2, 3, 2, 2, 1, 1, 4, 1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+                1, 1, 1, 1, 1, 1, 1, 2,
+            ],
+        ),
+        (Lang::Fr, Kind::Vowel) => ("a e é è i o u", &[8, 15, 2, 1, 7, 5, 6]),
+        (Lang::Fr, Kind::Consonant) => (
+            "b c d f g h j l m n p q r s t v x z",
+            &[1, 3, 4, 1, 1, 1, 1, 5, 3, 7, 3, 1, 7, 8, 7, 2, 1, 1],
+        ),
+        (Lang::De, Kind::Vowel) => ("a e i o u ä ö ü", &[6, 17, 8, 3, 4, 1, 1, 1]),
+        (Lang::De, Kind::Consonant) => (
+            "b c d f g h k l m n p r s t w z ß",
+            &[2, 3, 5, 2, 3, 5, 1, 3, 3, 10, 1, 7, 7, 6, 2, 1, 1],
+        ),
+        (Lang::It, Kind::Vowel) => ("a e i o u", &[12, 12, 11, 10, 3]),
+        (Lang::It, Kind::Consonant) => (
+            "b c d f g l m n p q r s t v z",
+            &[1, 5, 4, 1, 2, 7, 3, 7, 3, 1, 6, 5, 6, 2, 1],
+        ),
+        (Lang::Ru, Kind::Vowel) => ("а е ё и о у ы э ю я", &[8, 8, 1, 7, 11, 3, 2, 1, 1, 2]),
+        (Lang::Ru, Kind::Consonant) => (
+            "б в г д ж з й к л м н п р с т ф х ц ч ш щ ь ъ",
+            &[
+                2, 5, 2, 3, 1, 2, 1, 3, 4, 3, 7, 3, 5, 5, 6, 1, 1, 1, 1, 1, 1, 2, 1,
+            ],
+        ),
+        (Lang::Eo, Kind::Vowel) => ("a e i o u", &[12, 9, 10, 9, 3]),
+        (Lang::Eo, Kind::Consonant) => (
+            "b c ĉ d f g ĝ h ĥ j ĵ k l m n p r s ŝ t ŭ v z",
+            &[
+                1, 1, 1, 3, 1, 1, 1, 1, 1, 3, 1, 4, 6, 3, 8, 3, 6, 6, 1, 5, 1, 1, 1,
+            ],
+        ),
+    }
+}
+
+fn draw_letter(lang: Lang, kind: Kind) -> String {
+    let (letters, weights) = table(lang, kind);
+    let letters = letters.split(' ').collect::<Vec<_>>();
+    let index = WeightedIndex::new(weights).unwrap();
+    letters[index.sample(&mut rand::thread_rng())].to_string()
+}
+
+/// Counts the graphemes, where kana are normalized so that katakana words can be made of hiragana.
+fn multiset<'a, I: Iterator<Item = &'a str>>(graphemes: I) -> HashMap<String, usize> {
+    let mut counts = HashMap::new();
+    for grapheme in graphemes {
+        *counts.entry(kana::normalize(grapheme)).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Whether `word` can be made of the letters, each of which is used at most once.
+fn is_made_of(word: &str, letters: &HashMap<String, usize>) -> bool {
+    multiset(UnicodeSegmentation::graphemes(word, true))
+        .iter()
+        .all(|(grapheme, count)| letters.get(grapheme).map_or(false, |have| have >= count))
+}
+
+fn length(word: &str) -> usize {
+    UnicodeSegmentation::graphemes(word, true).count()
+}
+
+/// A letters round: the letters are drawn one by one, then everyone submits the longest word of them.
+#[derive(Debug)]
+pub struct Letters {
+    pub lang: Lang,
+    pub drawn: Vec<(String, Kind)>,
+    /// The longest word submitted by each player, with the name.
+    pub words: IndexMap<u64, (String, String)>,
+    /// When all the letters are drawn.
+    pub started: Option<Instant>,
+}
+
+impl Letters {
+    fn new(lang: Lang) -> Self {
+        Letters {
+            lang,
+            drawn: Vec::new(),
+            words: IndexMap::new(),
+            started: None,
+        }
+    }
+
+    pub fn as_string(&self) -> String {
+        self.drawn.iter().map(|(letter, _)| letter).join(" ")
+    }
+
+    fn count(&self, kind: Kind) -> usize {
+        self.drawn
+            .iter()
+            .filter(|(_, drawn)| *drawn == kind)
+            .count()
+    }
+
+    /// Whether drawing `kind` still leaves enough room for the minimum number of the other kind.
+    fn can_draw(&self, kind: Kind) -> bool {
+        let rest = LETTER_COUNT - self.drawn.len() - 1;
+        match kind {
+            Kind::Vowel => self.count(Kind::Consonant) + rest >= MIN_CONSONANTS,
+            Kind::Consonant => self.count(Kind::Vowel) + rest >= MIN_VOWELS,
+        }
+    }
+
+    fn letters(&self) -> HashMap<String, usize> {
+        multiset(self.drawn.iter().map(|(letter, _)| letter.as_str()))
+    }
+
+    /// One of the longest words of `questions` made of the letters.
+    fn longest(&self) -> Option<&str> {
+        let letters = self.letters();
+        bot::get_dictionary(self.lang)
+            .iter()
+            .filter(|word| is_made_of(word, &letters))
+            .max_by_key(|word| length(word))
+    }
+
+    fn report(&self) -> String {
+        use ordinal::Ordinal;
+        let ranked = self
+            .words
+            .values()
+            .sorted_by_key(|(_, word)| std::cmp::Reverse(length(word)))
+            .collect::<Vec<_>>();
+        let best = ranked.first().map_or(0, |(_, word)| length(word));
+        let mut response = format!("レターズが終了しました。\n文字: {}\n", self.as_string());
+        let mut rank = 1;
+        for (index, (name, word)) in ranked.iter().enumerate() {
+            if index > 0 && length(word) < length(&ranked[index - 1].1) {
+                rank = index + 1;
+            }
+            response += &format!(
+                "{}: {}, {} ({} 文字)\n",
+                Ordinal(rank).to_string(),
+                name,
+                word,
+                length(word)
+            );
+        }
+        if ranked.is_empty() {
+            response += "有効な単語はありませんでした。\n";
+        } else {
+            response += &format!(
+                "{} さんに {} 点！\n",
+                ranked
+                    .iter()
+                    .filter(|(_, word)| length(word) == best)
+                    .map(|(name, _)| name)
+                    .join(", "),
+                best
+            );
+        }
+        if let Some(word) = self.longest() {
+            response += &format!("最長の例: {} ({} 文字)", word, length(word));
+        }
+        response
+    }
+}
+
+fn say(http: &Http, channel: ChannelId, response: String) {
+    if let Err(why) = channel.say(http, response) {
+        println!("{}", why);
+    }
+}
+
+pub(crate) fn start(ctx: &mut Context, msg: &Message, quiz: &mut Status, lang: Lang) {
+    try_say!(
+        ctx,
+        msg,
+        format!(
+            "レターズ ({symbol}) を始めます。`~v` で母音、`~c` で子音を引いて {count} 文字を揃えてください。\n母音は {vowels} 文字以上、子音は {consonants} 文字以上必要です。",
+            symbol = lang.as_symbol(),
+            count = LETTER_COUNT,
+            vowels = MIN_VOWELS,
+            consonants = MIN_CONSONANTS,
+        )
+    );
+    *quiz = Status::Lettering(Letters::new(lang));
+}
+
+/// Draws a letter of `kind`, and opens the submissions once all the letters are drawn.
+pub(crate) fn draw(http: &Arc<Http>, channel: ChannelId, quiz: &mut Status, kind: Kind) {
+    let letters = match quiz {
+        Status::Lettering(letters) if letters.started.is_none() => letters,
+        Status::Lettering(_) => {
+            say(http, channel, "文字はすべて引かれています。".to_string());
+            return;
+        }
+        _ => {
+            say(
+                http,
+                channel,
+                "レターズが始まっていません。`~letters` で始めてください。".to_string(),
+            );
+            return;
+        }
+    };
+    if !letters.can_draw(kind) {
+        say(
+            http,
+            channel,
+            match kind {
+                Kind::Vowel => "残りは子音を引いてください。".to_string(),
+                Kind::Consonant => "残りは母音を引いてください。".to_string(),
+            },
+        );
+        return;
+    }
+    letters.drawn.push((draw_letter(letters.lang, kind), kind));
+    if letters.drawn.len() < LETTER_COUNT {
+        say(
+            http,
+            channel,
+            format!(
+                "`{}` ({}/{})",
+                letters.as_string(),
+                letters.drawn.len(),
+                LETTER_COUNT
+            ),
+        );
+        return;
+    }
+    let started = Instant::now();
+    letters.started = Some(started);
+    say(
+        http,
+        channel,
+        format!(
+            "`{letters}`\nこれらの文字から作れる最も長い {symbol} を {secs} 秒以内に答えてください！ (同じ文字は引いた数まで)",
+            letters = letters.as_string(),
+            symbol = letters.lang.as_symbol(),
+            secs = TIME_LIMIT.as_secs(),
+        ),
+    );
+    let http = http.clone();
+    thread::spawn(move || {
+        thread::sleep(TIME_LIMIT);
+        let quiz = &mut *bot::QUIZ.lock().unwrap();
+        match quiz {
+            Status::Lettering(letters) if letters.started == Some(started) => {
+                say(&http, channel, letters.report());
+                *quiz = Status::StandingBy;
+            }
+            _ => {}
+        }
+    });
+}
+
+/// Accepts the message as a submission if it is a word made of the letters and longer than
+/// the previous one of the author. The word is deleted so that it is not revealed to others.
+pub(crate) fn answer_check(ctx: &mut Context, msg: &Message, quiz: &mut Status) {
+    let letters = match quiz {
+        Status::Lettering(letters) if letters.started.is_some() => letters,
+        _ => return,
+    };
+    let dic = bot::get_dictionary(letters.lang);
+    let word = dic.normalize(msg.content.trim());
+    if !is_made_of(&word, &letters.letters()) || !(dic.contains(&word) || dic.contains_ex(&word)) {
+        return;
+    }
+    let author = *msg.author.id.as_u64();
+    if let Some((_, previous)) = letters.words.get(&author) {
+        if length(previous) >= length(&word) {
+            return;
+        }
+    }
+    if let Err(why) = msg.channel_id.delete_message(&ctx, msg.id) {
+        println!("{}", why);
+    }
+    try_say!(
+        ctx,
+        msg,
+        format!(
+            "{} さんの {} 文字の単語を受け付けました。",
+            &msg.author.name,
+            length(&word)
+        )
+    );
+    letters
+        .words
+        .insert(author, (msg.author.name.clone(), word));
+}
+
+/// Ends the round at once: before all the letters are drawn it is cancelled, otherwise scored.
+pub(crate) fn giveup(ctx: &mut Context, msg: &Message, quiz: &mut Status) {
+    if let Status::Lettering(letters) = std::mem::replace(quiz, Status::StandingBy) {
+        if letters.started.is_some() {
+            try_say!(ctx, msg, letters.report());
+        } else {
+            try_say!(ctx, msg, "レターズを中止しました。");
+        }
+    }
+}
@@ -25,13 +25,36 @@ use std::env;
 
 pub mod bot;
 pub mod commands;
+pub mod coop;
+pub mod daily;
 pub mod dictionary;
+pub mod double;
+pub mod duel;
 pub mod error;
+pub mod history;
+pub mod hunt;
+pub mod import;
+pub mod index;
+pub mod kana;
+pub mod letters;
+pub mod normalize;
+pub mod practice;
+pub mod problem_set;
+pub mod puzzle;
+pub mod rating;
+pub mod review;
+pub mod schedule;
 pub mod settings;
+pub mod shiritori;
 pub mod sort;
+pub mod stats;
+pub mod survival;
+pub mod team;
+pub mod tournament;
 use sort::Sorted;
 
-use commands::{executors, facade};
+use commands::{executors, facade, parser};
+use serenity::model::channel::Reaction;
 use serenity::model::id::ChannelId;
 
 #[macro_export]
@@ -43,6 +66,9 @@ macro_rules! try_say {
     };
 }
 
+/// Commands which do not use dictionaries, and so are accepted while they are loaded.
+const WITHOUT_DICTIONARY: [&str; 5] = ["stats", "bracket", "unrated", "disable", "leave"];
+
 struct Handler;
 
 impl EventHandler for Handler {
@@ -52,11 +78,33 @@ impl EventHandler for Handler {
                 .say(&ctx, "おはようございます。 botの起動をおしらせします！")
                 .expect("fail to send");
         }
+        schedule::spawn(ctx.http.clone());
         println!("{} is connected!", ready.user.name);
     }
+
+    fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        duel::accept_reaction(&ctx.http, &reaction);
+    }
 }
 
 fn main() {
+    // `import` converts word lists into dictionaries instead of running the bot
+    if env::args().nth(1).as_deref() == Some("import") {
+        if let Err(why) = import::run(env::args().skip(1)) {
+            println!("{}", why);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // `compile` compiles dictionaries into indices loaded at startup
+    if env::args().nth(1).as_deref() == Some("compile") {
+        if let Err(why) = index::run(env::args().skip(1)) {
+            println!("{}", why);
+            std::process::exit(1);
+        }
+        return;
+    }
+    dictionary::preload();
     // Login with a bot token from the environment
     let mut client = Client::new(&env::var("DISCORD_TOKEN").expect("token"), Handler)
         .expect("Error creating client");
@@ -69,33 +117,100 @@ fn main() {
                 if command_name == "enable" {
                     return true;
                 }
-                if !settings::SETTINGS
-                    .lock()
-                    .unwrap()
-                    .channel
-                    .enabled
-                    .contains(msg.channel_id.as_u64())
+                let is_private_command = ["problemset", "daily", "practice", "review"]
+                    .contains(&command_name)
+                    && msg.is_private();
+                if !is_private_command
+                    && !settings::SETTINGS
+                        .lock()
+                        .unwrap()
+                        .channel
+                        .enabled
+                        .contains(msg.channel_id.as_u64())
                 {
                     return false;
                 }
-                if facade::QUIZ_COMMANDS_REGEX.is_match(&command_name.to_string()) {
+                if !dictionary::is_ready() && !WITHOUT_DICTIONARY.contains(&command_name) {
+                    try_say!(
+                        ctx,
+                        msg,
+                        "辞書を読み込み中です。しばらくしてからもう一度お試しください。"
+                    );
+                    return false;
+                }
+                // management subcommands of `~contest` are allowed while a quiz is held.
+                let is_management =
+                    command_name == "contest" && parser::contest_subcommand(&msg.content).is_some();
+                if facade::QUIZ_COMMANDS_REGEX.is_match(&command_name.to_string()) && !is_management
+                {
                     match &*bot::QUIZ.lock().unwrap() {
-                        bot::Status::Holding(ref ans, ..) => {
+                        bot::Status::Holding(ref ans, _, _, ref puzzle) => {
+                            try_say!(
+                                ctx,
+                                msg,
+                                format!(
+                                    "前回の出題が解かれていません\n問題: {}",
+                                    puzzle.display(ans)
+                                )
+                            );
+                            false
+                        }
+                        bot::Status::Contesting(ref ans, _, _, _, ref puzzle) => {
+                            try_say!(
+                                ctx,
+                                msg,
+                                format!("現在コンテスト中です\n問題: {}", puzzle.display(ans))
+                            );
+                            false
+                        }
+                        bot::Status::Surviving(ref ans, ..) => {
+                            try_say!(
+                                ctx,
+                                msg,
+                                format!("現在サバイバル中です\n問題: {}", ans.sorted())
+                            );
+                            false
+                        }
+                        bot::Status::Chaining(ref ans, ..) => {
                             try_say!(
                                 ctx,
                                 msg,
-                                format!("前回の出題が解かれていません\n問題: {}", ans.sorted())
+                                format!("現在しりとり中です\n問題: {}", ans.sorted())
                             );
                             false
                         }
-                        bot::Status::Contesting(ref ans, ..) => {
+                        bot::Status::Lettering(ref letters) => {
                             try_say!(
                                 ctx,
                                 msg,
-                                format!("現在コンテスト中です\n問題: {}", ans.sorted())
+                                format!("現在レターズ中です\n文字: {}", letters.as_string())
                             );
                             false
                         }
+                        bot::Status::Hunting(ref hunt) => {
+                            try_say!(
+                                ctx,
+                                msg,
+                                format!("現在アナグラムハント中です\n文字: {}", hunt.ans.sorted())
+                            );
+                            false
+                        }
+                        bot::Status::Doubling(ref double) => {
+                            try_say!(
+                                ctx,
+                                msg,
+                                format!("前回の出題が解かれていません\n問題: {}", double.sorted)
+                            );
+                            false
+                        }
+                        bot::Status::Cooperating(..) => {
+                            try_say!(ctx, msg, "現在協力モード中です。");
+                            false
+                        }
+                        bot::Status::Recruiting(..) => {
+                            try_say!(ctx, msg, "現在コンテストの参加者を募集中です。");
+                            false
+                        }
                         bot::Status::StandingBy => true,
                     }
                 } else {
@@ -116,6 +231,9 @@ fn main() {
             .group(&commands::facade::CONTEST_GROUP)
             .group(&commands::facade::SETTINGS_GROUP)
             .group(&commands::facade::EXTRA_GROUP)
+            .group(&commands::facade::DUEL_GROUP)
+            .group(&commands::facade::TOURNAMENT_GROUP)
+            .group(&commands::facade::LETTERS_GROUP)
             .help(&commands::facade::NAZONAZO_HELP),
     );
 
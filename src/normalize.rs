@@ -0,0 +1,89 @@
+use serde_derive::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+pub use super::dictionary::Form;
+
+/// How words of a dictionary and the answers in its language are compared.
+/// Each dictionary can choose it with `normalization` and `case_fold`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Normalization {
+    pub form: Form,
+    pub case_fold: bool,
+}
+
+impl Default for Normalization {
+    fn default() -> Self {
+        Normalization {
+            form: Form::Nfkc,
+            case_fold: true,
+        }
+    }
+}
+
+impl Normalization {
+    pub fn apply(self, word: &str) -> String {
+        let composed = match self.form {
+            Form::Nfc => word.nfc().collect::<String>(),
+            Form::Nfkc => word.nfkc().collect::<String>(),
+        };
+        if self.case_fold {
+            // lowercase rather than full case folding, which turns "ß" into "ss" and changes the graphemes.
+            // lowercasing may decompose a letter such as "İ", so it is composed again.
+            composed.to_lowercase().nfc().collect()
+        } else {
+            composed
+        }
+    }
+}
+
+/// Canonical composition, after which equivalent strings consist of the same graphemes.
+pub fn nfc(word: &str) -> String {
+    word.nfc().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::Sorted;
+    use unicode_segmentation::UnicodeSegmentation;
+
+    #[test]
+    fn decomposed_letters_are_composed() {
+        let normalization = Normalization::default();
+        assert_eq!(normalization.apply("c\u{302}ambro"), "ĉambro");
+        assert_eq!(normalization.apply("か\u{3099}"), "が");
+        assert_eq!(
+            "き\u{304b}\u{3099}".to_string().sorted(),
+            "がき".to_string().sorted()
+        );
+        let nfc = Normalization {
+            form: Form::Nfc,
+            case_fold: false,
+        };
+        assert_eq!(nfc.apply("C\u{302}ambro"), "Ĉambro");
+        assert_eq!(nfc.apply("か\u{3099}"), "が");
+    }
+
+    #[test]
+    fn compatibility_letters_are_mapped_by_nfkc() {
+        assert_eq!(Normalization::default().apply("ＡＰＰＬＥ"), "apple");
+        assert_eq!(Normalization::default().apply("ｶﾞ"), "ガ");
+        let nfc = Normalization {
+            form: Form::Nfc,
+            case_fold: true,
+        };
+        assert_eq!(nfc.apply("ＡＰＰＬＥ"), "ａｐｐｌｅ");
+    }
+
+    #[test]
+    fn lowercasing_keeps_graphemes() {
+        // "İ" is lowercased into "i" and a combining dot, which is still one grapheme.
+        let lowered = Normalization::default().apply("İstanbul");
+        assert_eq!(lowered, "i\u{307}stanbul");
+        assert_eq!(
+            lowered.graphemes(true).count(),
+            "İstanbul".graphemes(true).count()
+        );
+        assert_eq!(Normalization::default().apply(&lowered), lowered);
+    }
+}
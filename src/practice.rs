@@ -0,0 +1,307 @@
+use super::bot::{self, ContestData, Lang};
+use super::puzzle;
+use super::review::{self, Grade};
+use super::sort::Sorted;
+use super::stats;
+use crate::try_say;
+use serenity::client::Context;
+use serenity::model::channel::Message;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const NAMES: [&'static str; 3] = ["easy", "normal", "hard"];
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    /// Number of graphemes of the answers.
+    fn length(self) -> RangeInclusive<usize> {
+        match self {
+            Difficulty::Easy => 1..=5,
+            Difficulty::Normal => 5..=8,
+            Difficulty::Hard => 8..=usize::max_value(),
+        }
+    }
+}
+
+/// A quiz played alone in DM. It is kept apart from the quiz of channels and never rated.
+#[derive(Debug)]
+pub(crate) struct Practice {
+    lang: Lang,
+    difficulty: Difficulty,
+    /// Seconds aimed at for each problem.
+    target: Option<u32>,
+    /// The current problem, which is always `Status::Contesting`.
+    quiz: bot::Status,
+    result: ContestData,
+    skipped: u32,
+    /// Words due for review, asked before any other, the last first.
+    queue: Vec<(Lang, String)>,
+}
+
+lazy_static! {
+    /// Sessions keyed by user id.
+    pub(crate) static ref PRACTICES: Arc<Mutex<HashMap<u64, Practice>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+impl Practice {
+    /// Picks the answer of the next problem. `count` is the number of problems asked so far.
+    fn next_problem(&mut self, count: u32, num: u32) -> String {
+        let (lang, ans) = self.queue.pop().unwrap_or_else(|| {
+            let dic = bot::get_dictionary(self.lang);
+            let rng = &mut rand::thread_rng();
+            let range = self.difficulty.length();
+            let ans = dic
+                .get_except(rng, |word| {
+                    !range.contains(&UnicodeSegmentation::graphemes(word, true).count())
+                })
+                .unwrap_or_else(|| dic.get(rng));
+            (self.lang, ans.to_string())
+        });
+        let response = format!(
+            "練習 問 {current} ({current}/{number})\nソート前の {symbol} な〜んだ？\n`{prob}`",
+            current = count + 1,
+            number = num,
+            symbol = lang.as_symbol(),
+            prob = ans.sorted(),
+        );
+        self.quiz = bot::Status::Contesting(
+            ans,
+            lang,
+            (count + 1, num),
+            Instant::now(),
+            Box::new(puzzle::Sorted),
+        );
+        response
+    }
+
+    /// The language and the answer of the current problem.
+    fn current(&self) -> (Lang, String) {
+        match &self.quiz {
+            bot::Status::Contesting(ans, lang, ..) => (*lang, ans.clone()),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Answers slower than the target, if any, are graded as hard.
+    fn grade(&self, elapsed: f32) -> Grade {
+        let slow = self
+            .target
+            .map_or(review::SLOW_SECS, |target| target as f32);
+        if elapsed > slow {
+            Grade::Hard
+        } else {
+            Grade::Good
+        }
+    }
+
+    fn on_target(&self) -> usize {
+        match self.target {
+            Some(target) => self
+                .result
+                .time
+                .iter()
+                .filter(|time| **time <= target as f32)
+                .count(),
+            None => 0,
+        }
+    }
+
+    fn report(&self) -> String {
+        let num = *self.quiz.get_contest_num().unwrap().1;
+        let solved = self.result.time.len();
+        let mut response = format!(
+            "練習が終了しました。\n正解 {} / {} (スキップ {})",
+            solved, num, self.skipped
+        );
+        if solved > 0 {
+            response += &format!(
+                "\n平均 {:.3} sec, 最速 {:.3} sec",
+                self.result.time.iter().sum::<f32>() / solved as f32,
+                self.result
+                    .time
+                    .iter()
+                    .cloned()
+                    .fold(f32::INFINITY, f32::min)
+            );
+        }
+        if let Some(target) = self.target {
+            response += &format!("\n目標 {} sec 以内: {} 問", target, self.on_target());
+        }
+        response
+    }
+}
+
+/// Starts a practice of the author, replacing the one in progress.
+pub(crate) fn start(
+    ctx: &mut Context,
+    msg: &Message,
+    lang: Lang,
+    num: u32,
+    difficulty: Difficulty,
+    target: Option<u32>,
+) {
+    let practice = Practice {
+        lang,
+        difficulty,
+        target,
+        quiz: bot::Status::StandingBy,
+        result: ContestData::default(),
+        skipped: 0,
+        queue: Vec::new(),
+    };
+    begin(ctx, msg, practice, num);
+}
+
+/// Starts a practice of the words due for review, which must not be empty.
+pub(crate) fn start_review(ctx: &mut Context, msg: &Message, mut words: Vec<(Lang, String)>) {
+    let num = words.len() as u32;
+    words.reverse();
+    let practice = Practice {
+        lang: words[0].0,
+        difficulty: Difficulty::Normal,
+        target: None,
+        quiz: bot::Status::StandingBy,
+        result: ContestData::default(),
+        skipped: 0,
+        queue: words,
+    };
+    begin(ctx, msg, practice, num);
+}
+
+fn begin(ctx: &mut Context, msg: &Message, mut practice: Practice, num: u32) {
+    let problem = practice.next_problem(0, num);
+    try_say!(
+        ctx,
+        msg,
+        format!(
+            "{} 問の練習を始めます。 `~practice skip` で次の問題へ、`~practice stop` で終了します。\n{}",
+            num, problem
+        )
+    );
+    PRACTICES
+        .lock()
+        .unwrap()
+        .insert(*msg.author.id.as_u64(), practice);
+}
+
+/// Asks the next problem, or ends the practice after the last one.
+fn proceed(ctx: &mut Context, msg: &Message, practices: &mut HashMap<u64, Practice>) {
+    let user = *msg.author.id.as_u64();
+    let practice = practices.get_mut(&user).unwrap();
+    let (count, num) = practice
+        .quiz
+        .get_contest_num()
+        .map(|(count, num)| (*count, *num))
+        .unwrap();
+    if count < num {
+        let problem = practice.next_problem(count, num);
+        try_say!(ctx, msg, problem);
+    } else {
+        finish(ctx, msg, practices);
+    }
+}
+
+/// Ends the practice of the author and saves the result to the personal statistics.
+fn finish(ctx: &mut Context, msg: &Message, practices: &mut HashMap<u64, Practice>) {
+    let user = *msg.author.id.as_u64();
+    if let Some(practice) = practices.remove(&user) {
+        try_say!(ctx, msg, practice.report());
+        let result = stats::update(&[user], |_, stats| {
+            stats.practices += 1;
+            stats.practice_solved += practice.result.time.len() as u32;
+            stats.practice_time += practice.result.time.iter().sum::<f32>();
+        });
+        if let Err(why) = result {
+            println!("{}", why);
+        }
+    }
+}
+
+/// Shows the answer and asks the next problem.
+pub(crate) fn skip(ctx: &mut Context, msg: &Message) -> bool {
+    let practices = &mut *PRACTICES.lock().unwrap();
+    let practice = match practices.get_mut(msg.author.id.as_u64()) {
+        Some(practice) => practice,
+        None => return false,
+    };
+    practice.skipped += 1;
+    let (lang, ans) = practice.current();
+    review::record(*msg.author.id.as_u64(), lang, &ans, Grade::Fail);
+    try_say!(
+        ctx,
+        msg,
+        format!(
+            "正解は \"{}\" でした。{}",
+            ans,
+            bot::get_dictionary(lang).describe(&ans)
+        )
+    );
+    proceed(ctx, msg, practices);
+    true
+}
+
+pub(crate) fn stop(ctx: &mut Context, msg: &Message) -> bool {
+    let practices = &mut *PRACTICES.lock().unwrap();
+    if !practices.contains_key(msg.author.id.as_u64()) {
+        return false;
+    }
+    finish(ctx, msg, practices);
+    true
+}
+
+pub(crate) fn is_practicing(user: u64) -> bool {
+    PRACTICES.lock().unwrap().contains_key(&user)
+}
+
+/// Checks an answer sent in DM. Returns false if the author is not practicing or it is wrong,
+/// in which case it may be meant for the daily puzzle.
+pub(crate) fn answer_check(ctx: &mut Context, msg: &Message) -> bool {
+    let practices = &mut *PRACTICES.lock().unwrap();
+    let practice = match practices.get_mut(msg.author.id.as_u64()) {
+        Some(practice) => practice,
+        None => return false,
+    };
+    if let bot::CheckResult::WA = practice.quiz.answer_check(&msg.content) {
+        return false;
+    }
+    let elapsed = practice.quiz.elapsed().unwrap();
+    practice.result += elapsed;
+    let (lang, ans) = practice.current();
+    review::record(*msg.author.id.as_u64(), lang, &ans, practice.grade(elapsed));
+    let mark = match practice.target {
+        Some(target) if elapsed <= target as f32 => " 目標達成！",
+        _ => "",
+    };
+    try_say!(
+        ctx,
+        msg,
+        format!(
+            "正解です！\n正解は\"{}\"でした！ [{:.3} sec]{}{}",
+            ans,
+            elapsed,
+            mark,
+            bot::get_dictionary(lang).describe(&ans)
+        )
+    );
+    proceed(ctx, msg, practices);
+    true
+}
@@ -0,0 +1,100 @@
+use super::bot::{self, Lang};
+use super::error::BotError;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// A list of contest problems, either exported from a past contest or prepared by an organizer.
+///
+/// Besides toml, a plain text format is accepted: one `<lang> <answer>` per line,
+/// where `#` starts a comment line.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProblemSet {
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub shuffle: bool,
+    pub problems: Vec<Problem>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Problem {
+    pub lang: String,
+    pub answer: String,
+}
+
+lazy_static! {
+    /// Problem sets registered by organizers via DM, keyed by user id.
+    pub static ref PROBLEM_SETS: Arc<Mutex<HashMap<u64, ProblemSet>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+}
+
+impl ProblemSet {
+    pub fn new(seed: u64, problems: &[(Lang, String)]) -> ProblemSet {
+        ProblemSet {
+            seed: Some(seed),
+            shuffle: false,
+            problems: problems
+                .iter()
+                .map(|(lang, answer)| Problem {
+                    lang: lang.as_code().to_string(),
+                    answer: answer.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn parse(buffer: &[u8]) -> Result<ProblemSet, BotError> {
+        let text = String::from_utf8_lossy(buffer);
+        let problem_set = if text.contains("[[problems]]") {
+            toml::from_slice(buffer)?
+        } else {
+            ProblemSet::from_text(&text)?
+        };
+        if problem_set.problems.is_empty() {
+            return Err(BotError::EmptyProblemSet);
+        }
+        // reject unknown languages before the set is used.
+        problem_set.problems()?;
+        Ok(problem_set)
+    }
+
+    fn from_text(text: &str) -> Result<ProblemSet, BotError> {
+        let problems = text
+            .lines()
+            .map(str::trim)
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(index, line)| {
+                let mut iter = line.splitn(2, |c: char| c.is_whitespace() || c == ':' || c == ',');
+                match (iter.next(), iter.next().map(str::trim)) {
+                    (Some(lang), Some(answer)) if !answer.is_empty() => Ok(Problem {
+                        lang: lang.to_string(),
+                        answer: answer.to_string(),
+                    }),
+                    _ => Err(BotError::InvalidLine(index + 1, line.to_string())),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ProblemSet {
+            seed: None,
+            shuffle: false,
+            problems,
+        })
+    }
+
+    pub fn to_toml(&self) -> Result<String, BotError> {
+        use quick_error::ResultExt;
+        Ok(toml::to_string(self).context("problem set")?)
+    }
+
+    pub fn problems(&self) -> Result<Vec<(Lang, String)>, BotError> {
+        self.problems
+            .iter()
+            .map(|problem| {
+                Lang::from_code(&problem.lang)
+                    .map(|lang| (lang, bot::get_dictionary(lang).normalize(&problem.answer)))
+                    .ok_or_else(|| BotError::UnknownLanguage(problem.lang.clone()))
+            })
+            .collect()
+    }
+}
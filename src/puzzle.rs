@@ -0,0 +1,324 @@
+use super::dictionary::Dictionary;
+use super::settings;
+use super::sort::Sorted as _;
+use rand::seq::SliceRandom;
+use serde_derive::{Deserialize, Serialize};
+use std::fmt::Debug;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Graphemes of each chunk of `Chunked`.
+const CHUNK_SIZE: usize = 3;
+
+const SHUFFLE_RETRY_LIMIT: usize = 8;
+
+/// How a problem is made from its answer, and which guesses it accepts.
+/// A puzzle is made for each problem, so it may hold what was drawn at random for it.
+pub trait Puzzle: Debug + Send {
+    fn kind(&self) -> Kind;
+
+    /// The problem shown to players.
+    fn display(&self, ans: &str) -> String;
+
+    /// Whether `got` is an arrangement the problem asks for.
+    /// Whether `got` is a word of the dictionary is checked by the caller.
+    fn accepts(&self, ans: &str, got: &str) -> bool {
+        ans.to_string().sorted() == got.to_string().sorted()
+    }
+
+    /// Graphemes which hints are taken from.
+    fn hints<'a>(&self, ans: &'a str) -> Vec<&'a str> {
+        graphemes(ans)
+    }
+
+    /// Shown with the answer after the problem is over.
+    fn reveal(&self) -> String {
+        String::new()
+    }
+}
+
+fn graphemes(word: &str) -> Vec<&str> {
+    UnicodeSegmentation::graphemes(word, true).collect()
+}
+
+/// Whether `longer` is `shorter` with exactly one grapheme added. Both must be sorted.
+fn has_one_more(longer: &str, shorter: &str) -> bool {
+    let (longer, shorter) = (graphemes(longer), graphemes(shorter));
+    if longer.len() != shorter.len() + 1 {
+        return false;
+    }
+    let mut rest = shorter.iter().peekable();
+    let mut skipped = 0;
+    for grapheme in longer {
+        if rest.peek() == Some(&&grapheme) {
+            rest.next();
+        } else {
+            skipped += 1;
+        }
+    }
+    skipped == 1 && rest.peek().is_none()
+}
+
+/// The graphemes of the answer, sorted.
+#[derive(Debug)]
+pub struct Sorted;
+
+impl Puzzle for Sorted {
+    fn kind(&self) -> Kind {
+        Kind::Sorted
+    }
+
+    fn display(&self, ans: &str) -> String {
+        ans.to_string().sorted()
+    }
+}
+
+/// The graphemes of the answer, sorted in the reverse order.
+#[derive(Debug)]
+pub struct Reversed;
+
+impl Puzzle for Reversed {
+    fn kind(&self) -> Kind {
+        Kind::Reversed
+    }
+
+    fn display(&self, ans: &str) -> String {
+        graphemes(&ans.to_string().sorted())
+            .into_iter()
+            .rev()
+            .collect()
+    }
+}
+
+/// The graphemes of the answer in a random order.
+#[derive(Debug)]
+pub struct Shuffled(String);
+
+impl Puzzle for Shuffled {
+    fn kind(&self) -> Kind {
+        Kind::Shuffled
+    }
+
+    fn display(&self, _ans: &str) -> String {
+        self.0.clone()
+    }
+}
+
+/// The answer cut into chunks of `CHUNK_SIZE` graphemes, each of which is sorted.
+#[derive(Debug)]
+pub struct Chunked;
+
+impl Chunked {
+    fn chunks(word: &str) -> Vec<String> {
+        graphemes(word)
+            .chunks(CHUNK_SIZE)
+            .map(|chunk| chunk.concat().sorted())
+            .collect()
+    }
+}
+
+impl Puzzle for Chunked {
+    fn kind(&self) -> Kind {
+        Kind::Chunked
+    }
+
+    fn display(&self, ans: &str) -> String {
+        Chunked::chunks(ans).join("/")
+    }
+
+    fn accepts(&self, ans: &str, got: &str) -> bool {
+        Chunked::chunks(ans) == Chunked::chunks(got)
+    }
+}
+
+/// Each space-separated word of the answer sorted on its own.
+#[derive(Debug)]
+pub struct PerWord;
+
+impl PerWord {
+    fn words(phrase: &str) -> Vec<String> {
+        phrase
+            .split(' ')
+            .map(|word| word.to_string().sorted())
+            .collect()
+    }
+}
+
+impl Puzzle for PerWord {
+    fn kind(&self) -> Kind {
+        Kind::PerWord
+    }
+
+    fn display(&self, ans: &str) -> String {
+        PerWord::words(ans).join(" ")
+    }
+
+    fn accepts(&self, ans: &str, got: &str) -> bool {
+        PerWord::words(ans) == PerWord::words(got)
+    }
+}
+
+/// The answer with this grapheme added, sorted.
+/// Any grapheme may be the extra one, so other words than the answer are accepted.
+#[derive(Debug)]
+pub struct Decoy(String);
+
+impl Puzzle for Decoy {
+    fn kind(&self) -> Kind {
+        Kind::Decoy
+    }
+
+    fn display(&self, ans: &str) -> String {
+        (ans.to_string() + &self.0).sorted()
+    }
+
+    fn accepts(&self, ans: &str, got: &str) -> bool {
+        has_one_more(&self.display(ans), &got.to_string().sorted())
+    }
+
+    fn reveal(&self) -> String {
+        format!(" (余分な文字: {})", self.0)
+    }
+}
+
+/// The answer with this grapheme removed, sorted.
+/// Any grapheme may be the missing one, so other words than the answer are accepted.
+#[derive(Debug)]
+pub struct Missing(String);
+
+impl Puzzle for Missing {
+    fn kind(&self) -> Kind {
+        Kind::Missing
+    }
+
+    fn display(&self, ans: &str) -> String {
+        let mut graphemes = graphemes(ans);
+        let position = graphemes.iter().position(|g| *g == self.0).unwrap();
+        graphemes.remove(position);
+        graphemes.concat().sorted()
+    }
+
+    fn accepts(&self, ans: &str, got: &str) -> bool {
+        has_one_more(&got.to_string().sorted(), &self.display(ans))
+    }
+
+    fn reveal(&self) -> String {
+        format!(" (足りない文字: {})", self.0)
+    }
+}
+
+/// The puzzles which can be chosen by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Sorted,
+    Reversed,
+    Shuffled,
+    Chunked,
+    PerWord,
+    Decoy,
+    Missing,
+}
+
+impl Default for Kind {
+    fn default() -> Self {
+        Kind::Sorted
+    }
+}
+
+impl Kind {
+    pub const NAMES: [&'static str; 7] = [
+        "sorted", "reversed", "shuffled", "chunked", "perword", "decoy", "missing",
+    ];
+
+    pub fn from_name(name: &str) -> Option<Kind> {
+        match name {
+            "sorted" => Some(Kind::Sorted),
+            "reversed" => Some(Kind::Reversed),
+            "shuffled" => Some(Kind::Shuffled),
+            "chunked" => Some(Kind::Chunked),
+            "perword" => Some(Kind::PerWord),
+            "decoy" => Some(Kind::Decoy),
+            "missing" => Some(Kind::Missing),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Sorted => "sorted",
+            Kind::Reversed => "reversed",
+            Kind::Shuffled => "shuffled",
+            Kind::Chunked => "chunked",
+            Kind::PerWord => "perword",
+            Kind::Decoy => "decoy",
+            Kind::Missing => "missing",
+        }
+    }
+
+    /// The question of the problem statement.
+    pub fn statement(self) -> String {
+        match self {
+            Kind::Sorted => "ソート前の".to_string(),
+            Kind::Reversed => "逆順にソートする前の".to_string(),
+            Kind::Shuffled => "シャッフルする前の".to_string(),
+            Kind::Chunked => format!("{}文字ずつソートする前の", CHUNK_SIZE),
+            Kind::PerWord => "単語ごとにソートする前の".to_string(),
+            Kind::Decoy => "1文字だけ余分です。残りの文字でできる".to_string(),
+            Kind::Missing => "1文字足りません。1文字加えてできる".to_string(),
+        }
+    }
+
+    /// Minimum number of graphemes of the answer, so that the problem is not trivial.
+    pub fn min_length(self) -> usize {
+        match self {
+            Kind::Missing => 3,
+            _ => 1,
+        }
+    }
+
+    /// Makes the puzzle of `ans`. An answer too short for the kind is asked as `Sorted`.
+    pub fn make<Rng: rand::Rng>(
+        self,
+        ans: &str,
+        dic: &Dictionary,
+        rng: &mut Rng,
+    ) -> Box<dyn Puzzle> {
+        if graphemes(ans).len() < self.min_length() {
+            return Box::new(Sorted);
+        }
+        match self {
+            Kind::Sorted => Box::new(Sorted),
+            Kind::Reversed => Box::new(Reversed),
+            Kind::Shuffled => {
+                let mut shuffled = graphemes(ans);
+                // the answer itself is avoided unless all of the arrangements are the same.
+                for _ in 0..SHUFFLE_RETRY_LIMIT {
+                    shuffled.shuffle(rng);
+                    if shuffled.concat() != ans {
+                        break;
+                    }
+                }
+                Box::new(Shuffled(shuffled.concat()))
+            }
+            Kind::Chunked => Box::new(Chunked),
+            Kind::PerWord => Box::new(PerWord),
+            // a decoy is taken from another word, so that it looks like a letter of the language.
+            Kind::Decoy => {
+                let decoy = dic.get_except(rng, str::is_empty).unwrap_or(ans);
+                Box::new(Decoy(graphemes(decoy).choose(rng).unwrap().to_string()))
+            }
+            Kind::Missing => Box::new(Missing(graphemes(ans).choose(rng).unwrap().to_string())),
+        }
+    }
+}
+
+/// The puzzle chosen for `channel` by `~puzzle`, or `Sorted`.
+pub fn channel_default(channel: u64) -> Kind {
+    settings::SETTINGS
+        .lock()
+        .unwrap()
+        .puzzles
+        .get(&channel.to_string())
+        .copied()
+        .unwrap_or_default()
+}
@@ -0,0 +1,84 @@
+use super::bot::ContestData;
+use super::error::BotError;
+use super::settings;
+use serde_derive::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+const RATING_PATH: &str = "/tmp/settings/rating.toml";
+
+pub(crate) const INITIAL_RATING: i32 = 1500;
+
+const K_FACTOR: f64 = 32.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Rating {
+    pub(crate) rating: i32,
+    pub(crate) contests: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating {
+            rating: INITIAL_RATING,
+            contests: 0,
+        }
+    }
+}
+
+/// Contest ratings, keyed by user id like `Stats`.
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub(crate) struct Ratings {
+    users: BTreeMap<String, Rating>,
+}
+
+lazy_static! {
+    pub(crate) static ref RATINGS: Arc<Mutex<Ratings>> =
+        Arc::new(Mutex::new(settings::init_config(RATING_PATH).unwrap()));
+}
+
+impl Ratings {
+    pub(crate) fn get(&self, user: u64) -> i32 {
+        self.users
+            .get(&user.to_string())
+            .map_or(INITIAL_RATING, |rating| rating.rating)
+    }
+
+    /// Updates ratings by pairwise Elo over the final standings, where equal keys are draws.
+    pub(crate) fn update(&mut self, standings: &[(u64, ContestData)]) {
+        if standings.len() < 2 {
+            return;
+        }
+        let current = standings
+            .iter()
+            .map(|(user, _)| f64::from(self.get(*user)))
+            .collect::<Vec<_>>();
+        let weight = K_FACTOR / (standings.len() - 1) as f64;
+        for (i, (user, data)) in standings.iter().enumerate() {
+            let delta = current
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(j, opponent)| {
+                    let expected = 1.0 / (1.0 + 10f64.powf((opponent - current[i]) / 400.0));
+                    let score = match data.key().cmp(&standings[j].1.key()) {
+                        Ordering::Less => 1.0,
+                        Ordering::Equal => 0.5,
+                        Ordering::Greater => 0.0,
+                    };
+                    weight * (score - expected)
+                })
+                .sum::<f64>();
+            let rating = self.users.entry(user.to_string()).or_default();
+            rating.rating += delta.round() as i32;
+            rating.contests += 1;
+        }
+    }
+}
+
+pub(crate) fn update(standings: &[(u64, ContestData)]) -> Result<(), BotError> {
+    let ratings = &mut *RATINGS.lock().unwrap();
+    ratings.update(standings);
+    settings::sync_config(ratings, RATING_PATH)
+}
@@ -0,0 +1,163 @@
+use super::bot::Lang;
+use super::schedule;
+use super::settings;
+use itertools::Itertools;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+const REVIEW_PATH: &str = "/tmp/settings/review.toml";
+
+const INITIAL_EASE: f32 = 2.5;
+
+const MIN_EASE: f32 = 1.3;
+
+/// A card whose interval reaches this many days is counted as learned.
+const LEARNED_INTERVAL: u32 = 21;
+
+/// Answers slower than this are graded as hard unless the practice has a target.
+pub(crate) const SLOW_SECS: f32 = 20.0;
+
+/// How well a word was answered, in the grades of SM-2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Grade {
+    /// Given up.
+    Fail = 1,
+    /// Solved slowly.
+    Hard = 3,
+    Good = 5,
+}
+
+/// Scheduling state of a word for a user. Days are those of `schedule::today`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Card {
+    ease: f32,
+    /// Days until the next review.
+    interval: u32,
+    /// Successful reviews in a row.
+    repetitions: u32,
+    due: i64,
+    lapses: u32,
+}
+
+impl Default for Card {
+    fn default() -> Self {
+        Card {
+            ease: INITIAL_EASE,
+            interval: 0,
+            repetitions: 0,
+            due: 0,
+            lapses: 0,
+        }
+    }
+}
+
+impl Card {
+    /// Schedules the next review as SM-2 does.
+    fn review(&mut self, grade: Grade, today: i64) {
+        let q = grade as i32 as f32;
+        if grade == Grade::Fail {
+            self.repetitions = 0;
+            self.interval = 1;
+            self.lapses += 1;
+        } else {
+            self.repetitions += 1;
+            self.interval = match self.repetitions {
+                1 => 1,
+                2 => 6,
+                _ => (self.interval as f32 * self.ease).round() as u32,
+            };
+        }
+        self.ease = (self.ease + 0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02)).max(MIN_EASE);
+        self.due = today + self.interval as i64;
+    }
+
+    fn is_due(&self, today: i64) -> bool {
+        self.due <= today
+    }
+}
+
+/// Cards keyed by user id, language code and word, since toml keys have to be strings.
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub(crate) struct Reviews {
+    users: BTreeMap<String, BTreeMap<String, BTreeMap<String, Card>>>,
+}
+
+lazy_static! {
+    pub(crate) static ref REVIEWS: Arc<Mutex<Reviews>> =
+        Arc::new(Mutex::new(settings::init_config(REVIEW_PATH).unwrap()));
+}
+
+impl Reviews {
+    /// Words of `user` due today, the most overdue first.
+    pub(crate) fn due(&self, user: u64, lang: Option<Lang>) -> Vec<(Lang, String)> {
+        let today = schedule::today();
+        self.users
+            .get(&user.to_string())
+            .into_iter()
+            .flat_map(|langs| langs.iter())
+            .filter_map(|(code, cards)| Lang::from_code(code).map(|lang| (lang, cards)))
+            .filter(|(card_lang, _)| lang.map_or(true, |lang| lang == *card_lang))
+            .flat_map(|(lang, cards)| {
+                cards
+                    .iter()
+                    .filter(|(_, card)| card.is_due(today))
+                    .map(move |(word, card)| (card.due, lang, word.clone()))
+            })
+            .sorted_by_key(|(due, _, _)| *due)
+            .map(|(_, lang, word)| (lang, word))
+            .collect()
+    }
+
+    /// Due, learned and all cards of `user` for each language.
+    pub(crate) fn progress(&self, user: u64) -> String {
+        let today = schedule::today();
+        let langs = match self.users.get(&user.to_string()) {
+            Some(langs) if !langs.is_empty() => langs,
+            _ => return "復習するカードはまだありません。".to_string(),
+        };
+        langs
+            .iter()
+            .filter_map(|(code, cards)| Lang::from_code(code).map(|lang| (lang, cards)))
+            .map(|(lang, cards)| {
+                format!(
+                    "{}: 復習 {} 枚 / 定着 {} 枚 / 全 {} 枚",
+                    lang.as_symbol(),
+                    cards.values().filter(|card| card.is_due(today)).count(),
+                    cards
+                        .values()
+                        .filter(|card| card.interval >= LEARNED_INTERVAL)
+                        .count(),
+                    cards.len()
+                )
+            })
+            .join("\n")
+    }
+}
+
+/// Grades how `user` answered `word`. A new card is made only for a word which was not answered well,
+/// and a card is rescheduled whenever its word is asked.
+pub(crate) fn record(user: u64, lang: Lang, word: &str, grade: Grade) {
+    let reviews = &mut *REVIEWS.lock().unwrap();
+    let (user, code) = (user.to_string(), lang.as_code().to_string());
+    let known = reviews
+        .users
+        .get(&user)
+        .and_then(|langs| langs.get(&code))
+        .map_or(false, |cards| cards.contains_key(word));
+    if grade == Grade::Good && !known {
+        return;
+    }
+    reviews
+        .users
+        .entry(user)
+        .or_default()
+        .entry(code)
+        .or_default()
+        .entry(word.to_string())
+        .or_default()
+        .review(grade, schedule::today());
+    if let Err(why) = settings::sync_config(reviews, REVIEW_PATH) {
+        println!("{}", why);
+    }
+}
@@ -0,0 +1,324 @@
+use super::bot;
+use super::commands::{executors, parser};
+use super::error::BotError;
+use super::puzzle;
+use super::settings;
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use itertools::Itertools;
+use serde_derive::{Deserialize, Serialize};
+use serenity::http::Http;
+use serenity::model::id::{ChannelId, UserId};
+use std::sync::{Arc, Mutex, Once};
+use std::thread;
+use std::time::Duration;
+
+const SCHEDULE_PATH: &str = "/tmp/settings/schedule.toml";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A scheduled contest which could not start within this period after its start time
+/// (because the bot was down or another contest was running) is dropped.
+const MISSED_GRACE_SECS: i64 = 30 * 60;
+
+/// Datetimes without an explicit offset are read as JST.
+const DEFAULT_OFFSET_SECS: i32 = 9 * 60 * 60;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ScheduledContest {
+    pub(crate) id: u32,
+    pub(crate) channel: u64,
+    pub(crate) organizer: u64,
+    pub(crate) at: i64,
+    pub(crate) num: u32,
+    /// Languages in the `en:3` form accepted by `~contest`.
+    pub(crate) languages: Vec<String>,
+    pub(crate) quota: bool,
+    pub(crate) fresh: Option<u32>,
+    pub(crate) seed: Option<u64>,
+    /// Minutes before the start, of the reminders not posted yet.
+    pub(crate) reminders: Vec<u32>,
+    #[serde(default)]
+    pub(crate) postponed: bool,
+    /// The channel default at the start is used unless given.
+    #[serde(default)]
+    pub(crate) puzzle: Option<puzzle::Kind>,
+    #[serde(default)]
+    pub(crate) tag: Option<String>,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub(crate) struct Schedule {
+    next_id: u32,
+    contests: Vec<ScheduledContest>,
+}
+
+lazy_static! {
+    pub(crate) static ref SCHEDULE: Arc<Mutex<Schedule>> =
+        Arc::new(Mutex::new(settings::init_config(SCHEDULE_PATH).unwrap()));
+}
+
+pub(crate) fn timezone() -> FixedOffset {
+    FixedOffset::east(DEFAULT_OFFSET_SECS)
+}
+
+pub(crate) fn now() -> i64 {
+    Utc::now().timestamp()
+}
+
+/// Days from the common era in JST.
+pub(crate) fn today() -> i64 {
+    Utc::now()
+        .with_timezone(&timezone())
+        .date()
+        .num_days_from_ce() as i64
+}
+
+/// Parses `YYYY-MM-DDTHH:MM` (JST) or `YYYY-MM-DDTHH:MM+09:00` into a UNIX timestamp.
+pub(crate) fn parse_datetime(datetime: &str) -> Option<i64> {
+    DateTime::parse_from_str(datetime, "%Y-%m-%dT%H:%M%:z")
+        .map(|datetime| datetime.timestamp())
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(datetime, "%Y-%m-%dT%H:%M")
+                .ok()
+                .and_then(|naive| timezone().from_local_datetime(&naive).single())
+                .map(|datetime| datetime.timestamp())
+        })
+}
+
+pub(crate) fn format_datetime(at: i64) -> String {
+    timezone()
+        .timestamp(at, 0)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+impl ScheduledContest {
+    pub(crate) fn as_string(&self) -> String {
+        format!(
+            "#{id} {at} {num}問 ({languages})",
+            id = self.id,
+            at = format_datetime(self.at),
+            num = self.num,
+            languages = self.languages.join(","),
+        )
+    }
+}
+
+impl Schedule {
+    pub(crate) fn list(&self, channel: ChannelId) -> Vec<&ScheduledContest> {
+        self.contests
+            .iter()
+            .filter(|contest| contest.channel == *channel.as_u64())
+            .sorted_by_key(|contest| contest.at)
+            .collect()
+    }
+}
+
+pub(crate) fn add(
+    channel: ChannelId,
+    organizer: UserId,
+    option: parser::ScheduleOption,
+) -> Result<u32, BotError> {
+    let schedule = &mut *SCHEDULE.lock().unwrap();
+    let id = schedule.next_id + 1;
+    schedule.next_id = id;
+    schedule.contests.push(ScheduledContest {
+        id,
+        channel: *channel.as_u64(),
+        organizer: *organizer.as_u64(),
+        at: option.at,
+        num: option.contest.num.unwrap(),
+        languages: option
+            .contest
+            .languages
+            .iter()
+            .map(|(lang, weight)| format!("{}:{}", lang, weight))
+            .collect(),
+        quota: option.contest.quota,
+        fresh: option.contest.fresh,
+        seed: option.contest.seed,
+        reminders: option.remind,
+        postponed: false,
+        puzzle: option.contest.puzzle,
+        tag: option.contest.tag,
+    });
+    settings::sync_config(schedule, SCHEDULE_PATH)?;
+    Ok(id)
+}
+
+/// Removes the contest `id` of `channel` if `user` is its organizer.
+pub(crate) fn cancel(
+    channel: ChannelId,
+    id: u32,
+    user: UserId,
+) -> Result<Option<ScheduledContest>, BotError> {
+    let schedule = &mut *SCHEDULE.lock().unwrap();
+    let position = schedule.contests.iter().position(|contest| {
+        contest.id == id
+            && contest.channel == *channel.as_u64()
+            && contest.organizer == *user.as_u64()
+    });
+    let cancelled = position.map(|position| schedule.contests.remove(position));
+    if cancelled.is_some() {
+        settings::sync_config(schedule, SCHEDULE_PATH)?;
+    }
+    Ok(cancelled)
+}
+
+/// Starts polling the schedule. Only the first call spawns the thread,
+/// since `ready` is fired again on every reconnection.
+pub(crate) fn spawn(http: Arc<Http>) {
+    static START: Once = Once::new();
+    START.call_once(move || {
+        thread::spawn(move || loop {
+            tick(&http);
+            thread::sleep(POLL_INTERVAL);
+        });
+    });
+}
+
+fn say(http: &Http, channel: ChannelId, response: String) {
+    if let Err(why) = channel.say(http, response) {
+        println!("{}", why);
+    }
+}
+
+/// Posts the reminders due and launches the contests due.
+/// The schedule is not locked while messages are sent or the quiz is locked,
+/// and the contests due stay in it until they are launched, so that they can be listed and cancelled.
+fn tick(http: &Http) {
+    let now = now();
+    let (reminders, due) = {
+        let schedule = &mut *SCHEDULE.lock().unwrap();
+        let mut reminders = Vec::new();
+        let mut changed = false;
+        for contest in schedule.contests.iter_mut() {
+            let at = contest.at;
+            let due = contest
+                .reminders
+                .iter()
+                .any(|minutes| at - i64::from(*minutes) * 60 <= now);
+            if due {
+                // only one reminder is posted even if several are due after a downtime.
+                contest
+                    .reminders
+                    .retain(|minutes| at - i64::from(*minutes) * 60 > now);
+                changed = true;
+                if at > now {
+                    reminders.push((
+                        ChannelId::from(contest.channel),
+                        format!(
+                            "予定されたコンテスト {} はあと {} 分で開始します。",
+                            contest.as_string(),
+                            (at - now + 59) / 60
+                        ),
+                    ));
+                }
+            }
+        }
+        if changed {
+            if let Err(why) = settings::sync_config(schedule, SCHEDULE_PATH) {
+                println!("{}", why);
+            }
+        }
+        let due = schedule
+            .contests
+            .iter()
+            .filter(|contest| contest.at <= now)
+            .map(|contest| contest.id)
+            .collect::<Vec<_>>();
+        (reminders, due)
+    };
+    for (channel, reminder) in reminders {
+        say(http, channel, reminder);
+    }
+    for id in due {
+        let found = SCHEDULE
+            .lock()
+            .unwrap()
+            .contests
+            .iter()
+            .find(|contest| contest.id == id)
+            .cloned();
+        // it may have been cancelled since.
+        let mut contest = match found {
+            Some(contest) => contest,
+            None => continue,
+        };
+        let launched = launch(http, &mut contest, now);
+        let schedule = &mut *SCHEDULE.lock().unwrap();
+        if launched {
+            schedule.contests.retain(|contest| contest.id != id);
+        } else {
+            match schedule.contests.iter_mut().find(|stored| stored.id == id) {
+                Some(stored) if stored.postponed != contest.postponed => {
+                    stored.postponed = contest.postponed;
+                }
+                _ => continue,
+            }
+        }
+        if let Err(why) = settings::sync_config(schedule, SCHEDULE_PATH) {
+            println!("{}", why);
+        }
+    }
+}
+
+/// Starts a scheduled contest, or returns false if it has to wait.
+///
+/// If a quiz or a contest is running, the scheduled one is postponed until it ends
+/// (but no longer than `MISSED_GRACE_SECS`). A quiz is not closed by force,
+/// since it may be held in another channel, whose answer would be revealed here.
+fn launch(http: &Http, contest: &mut ScheduledContest, now: i64) -> bool {
+    let channel = ChannelId::from(contest.channel);
+    if now - contest.at > MISSED_GRACE_SECS {
+        say(
+            http,
+            channel,
+            format!(
+                "予定されたコンテスト #{} は開始できなかったため中止しました。",
+                contest.id
+            ),
+        );
+        return true;
+    }
+    let quiz = &mut *bot::QUIZ.lock().unwrap();
+    if !quiz.is_standing_by() {
+        if !contest.postponed {
+            say(
+                http,
+                channel,
+                format!(
+                    "現在出題中のため、予定されたコンテスト #{} は出題の終了後に開始します。",
+                    contest.id
+                ),
+            );
+            contest.postponed = true;
+        }
+        return false;
+    }
+    let library = &mut *bot::CONTEST_LIBRARY.lock().unwrap();
+    executors::set_contest_library(
+        library,
+        channel,
+        contest
+            .languages
+            .iter()
+            .map(|language| parser::parse_language(language))
+            .collect(),
+        contest.quota,
+        contest.fresh,
+        contest.seed,
+        contest.tag.as_deref(),
+    );
+    say(
+        http,
+        channel,
+        format!("予定されたコンテスト #{} を開始します。", contest.id),
+    );
+    let kind = contest
+        .puzzle
+        .unwrap_or_else(|| puzzle::channel_default(*channel.as_u64()));
+    executors::start_contest(http, channel, quiz, library, contest.num, kind);
+    true
+}
@@ -0,0 +1,43 @@
+//! The format of dictionary files, shared with the build script which validates the embedded ones.
+use serde_derive::{Deserialize, Serialize};
+
+/// Unicode normal form which words are composed into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Form {
+    /// Only canonical composition, so that a decomposed "が" or "ĉ" is the same as the composed one.
+    Nfc,
+    /// Also maps compatibility characters, such as full-width Latin letters and half-width kana.
+    Nfkc,
+}
+
+/// What is known about a word besides its spelling. Every field but `word` is optional.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Entry {
+    pub word: String,
+    /// Meaning or translation.
+    pub meaning: Option<String>,
+    /// Reading of a Japanese word.
+    pub reading: Option<String>,
+    /// Part of speech.
+    pub pos: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Source or licence of the entry.
+    pub source: Option<String>,
+}
+
+/// Besides plain `questions`, words can be given as `[[entries]]` tables, which are also asked.
+/// Other formats are read into this by `import`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct RawDictionary {
+    #[serde(default)]
+    pub(crate) questions: Vec<String>,
+    pub(crate) full: Option<Vec<String>>,
+    /// NFKC unless given.
+    pub(crate) normalization: Option<Form>,
+    /// Whether words are compared in lowercase, unless given they are.
+    pub(crate) case_fold: Option<bool>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) entries: Vec<Entry>,
+}
@@ -1,7 +1,11 @@
 pub mod permission;
 
+use super::error::BotError;
+use super::puzzle;
+use serde::{de::DeserializeOwned, Serialize};
 use serde_derive::{Deserialize, Serialize};
-use std::fs::File;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
 use std::io::{Read, Write};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
@@ -10,6 +14,9 @@ use toml;
 #[derive(Default, Debug, Serialize, Deserialize)]
 pub(crate) struct Config {
     pub(crate) channel: permission::Channel,
+    /// Puzzle chosen by `~puzzle`, keyed by the channel id.
+    #[serde(default)]
+    pub(crate) puzzles: HashMap<String, puzzle::Kind>,
 }
 
 lazy_static! {
@@ -18,20 +25,38 @@ lazy_static! {
     ));
 }
 
-pub(crate) fn init_config<ConfigPath: AsRef<Path>>(path: ConfigPath) -> std::io::Result<Config> {
+pub(crate) fn init_config<T, ConfigPath>(path: ConfigPath) -> std::io::Result<T>
+where
+    T: Default + Serialize + DeserializeOwned,
+    ConfigPath: AsRef<Path>,
+{
     File::open(&path).map_or_else(
         |_| {
             let mut f = File::create(&path)?;
-            let buffer = toml::to_string(&Config::default()).unwrap();
+            let buffer = toml::to_string(&T::default()).unwrap();
             f.write_all(buffer.as_bytes())?;
             f.sync_all()?;
-            Ok(Config::default())
+            Ok(T::default())
         },
         |mut file| {
             let mut buffer = String::new();
             file.read_to_string(&mut buffer)?;
-            let conf: Config = toml::from_slice(buffer.as_bytes())?;
+            let conf: T = toml::from_slice(buffer.as_bytes())?;
             Ok(conf)
         },
     )
 }
+
+pub(crate) fn sync_config<T: Serialize>(conf: &T, path: &'static str) -> Result<(), BotError> {
+    use quick_error::ResultExt;
+    let path_ref = Path::new(path);
+    let mut f = OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(path_ref)
+        .context(path_ref)?;
+    f.write_all(toml::to_string(conf).context(path)?.as_bytes())
+        .context(path_ref)?;
+    f.sync_all().context(path_ref)?;
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use super::bot::{self, CheckResult, ContestData, Lang, Status};
+use super::history;
+use super::kana;
+use super::sort::Sorted;
+use crate::try_say;
+use indexmap::{IndexMap, IndexSet};
+use itertools::Itertools;
+use serenity::client::Context;
+use serenity::model::channel::Message;
+use std::time::Instant;
+
+/// A Japanese word ending with this ends the chain.
+const FINAL_KANA: &str = "ん";
+
+#[derive(Debug, Default)]
+pub struct Chain {
+    /// Answers so far, in order.
+    pub words: Vec<String>,
+    pub used: IndexSet<String>,
+    pub scores: IndexMap<String, ContestData>,
+}
+
+impl Chain {
+    fn report(&self) -> String {
+        format!(
+            "しりとりが終了しました。 ({} 語)\n{}\n{}",
+            self.words.len(),
+            self.words.iter().join(" → "),
+            bot::aggregates(&self.scores, &[])
+        )
+    }
+}
+
+fn is_final(lang: Lang, word: &str) -> bool {
+    lang == Lang::Ja && kana::tail(word).map_or(false, |tail| tail == FINAL_KANA)
+}
+
+/// Posts `ans` as the next puzzle of the chain.
+fn ask(ctx: &mut Context, msg: &Message, quiz: &mut Status, ans: String, lang: Lang, chain: Chain) {
+    if let Err(why) = history::record(*msg.channel_id.as_u64(), lang, &ans) {
+        println!("{}", why);
+    }
+    try_say!(
+        ctx,
+        msg,
+        format!(
+            "しりとり {count} 語目\n「{head}」から始まる {symbol} な〜んだ？\n`{prob}`",
+            count = chain.words.len() + 1,
+            head = kana::head(&ans).unwrap_or_default(),
+            symbol = lang.as_symbol(),
+            prob = ans.sorted(),
+        )
+    );
+    *quiz = Status::Chaining(ans, lang, chain, Instant::now());
+}
+
+pub(crate) fn start(ctx: &mut Context, msg: &Message, quiz: &mut Status, lang: Lang) {
+    let dic = bot::get_dictionary(lang);
+    let rng = &mut rand::thread_rng();
+    let ans = dic
+        .get_except(rng, |word| is_final(lang, word))
+        .unwrap_or_else(|| dic.get(rng))
+        .to_string();
+    ask(ctx, msg, quiz, ans, lang, Chain::default());
+}
+
+/// Accepts an answer of the chain, and asks a word starting with its last letter.
+pub(crate) fn answer_check(ctx: &mut Context, msg: &Message, quiz: &mut Status) {
+    let elapsed = quiz.elapsed();
+    let word = match quiz.answer_check(&msg.content) {
+        CheckResult::WA => return,
+        CheckResult::Assumed(word) | CheckResult::Anagram(word) | CheckResult::Full(word) => {
+            quiz.get_dictionary().unwrap().normalize(word)
+        }
+    };
+    let (lang, mut chain) = match std::mem::replace(quiz, Status::StandingBy) {
+        Status::Chaining(_, lang, chain, _) => (lang, chain),
+        other => {
+            *quiz = other;
+            return;
+        }
+    };
+    *chain
+        .scores
+        .entry(msg.author.name.clone())
+        .or_insert(ContestData::default()) += elapsed.unwrap();
+    chain.words.push(word.clone());
+    chain.used.insert(word.clone());
+    try_say!(
+        ctx,
+        msg,
+        format!(
+            "{} さん、正解です！\n\"{}\" [{:.3} sec]",
+            &msg.author.name,
+            word,
+            elapsed.unwrap(),
+        )
+    );
+    if is_final(lang, &word) {
+        try_say!(
+            ctx,
+            msg,
+            format!("「{}」で終わりました。\n{}", FINAL_KANA, chain.report())
+        );
+        return;
+    }
+    let tail = kana::tail(&word).unwrap_or_default();
+    let dic = bot::get_dictionary(lang);
+    let rng = &mut rand::thread_rng();
+    let used = &chain.used;
+    // words ending the chain are asked only when nothing else is left.
+    let next = dic
+        .get_starting_with(rng, &tail, |word| {
+            used.contains(word) || is_final(lang, word)
+        })
+        .or_else(|| dic.get_starting_with(rng, &tail, |word| used.contains(word)))
+        .map(str::to_string);
+    match next {
+        Some(ans) => ask(ctx, msg, quiz, ans, lang, chain),
+        None => try_say!(
+            ctx,
+            msg,
+            format!(
+                "「{}」から始まる単語がもうありません。\n{}",
+                tail,
+                chain.report()
+            )
+        ),
+    }
+}
+
+/// Ends the chain with the answer of the current puzzle shown.
+pub(crate) fn giveup(ctx: &mut Context, msg: &Message, quiz: &mut Status) {
+    if let Status::Chaining(ans, _, chain, _) = std::mem::replace(quiz, Status::StandingBy) {
+        try_say!(
+            ctx,
+            msg,
+            format!("正解は \"{}\" でした...\n{}", ans, chain.report())
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use super::normalize;
 use itertools::Itertools;
 use unicode_segmentation::UnicodeSegmentation;
 
@@ -6,8 +7,9 @@ pub trait Sorted {
 }
 
 impl Sorted for String {
+    /// Composed first, so that canonically equivalent strings are sorted into the same one.
     fn sorted(&self) -> Self {
-        UnicodeSegmentation::graphemes(self.as_str(), true)
+        UnicodeSegmentation::graphemes(normalize::nfc(self).as_str(), true)
             .sorted()
             .collect::<String>()
     }
@@ -0,0 +1,75 @@
+use super::error::BotError;
+use super::settings;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+const STATS_PATH: &str = "/tmp/settings/stats.toml";
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub(crate) struct UserStats {
+    pub(crate) duels: u32,
+    pub(crate) wins: u32,
+    pub(crate) losses: u32,
+    pub(crate) draws: u32,
+    /// Problems solved in duels.
+    pub(crate) solved: u32,
+    /// Practices played alone in DM, which are never rated.
+    #[serde(default)]
+    pub(crate) practices: u32,
+    #[serde(default)]
+    pub(crate) practice_solved: u32,
+    /// Total seconds of the problems solved in practices.
+    #[serde(default)]
+    pub(crate) practice_time: f32,
+}
+
+impl UserStats {
+    pub(crate) fn as_string(&self) -> String {
+        let mut response = format!(
+            "決闘: {}戦 {}勝 {}敗 {}分 (正解数 {})",
+            self.duels, self.wins, self.losses, self.draws, self.solved
+        );
+        if self.practice_solved > 0 {
+            response += &format!(
+                "\n練習: {}回 正解数 {} (平均 {:.3} sec)",
+                self.practices,
+                self.practice_solved,
+                self.practice_time / self.practice_solved as f32
+            );
+        }
+        response
+    }
+}
+
+/// Per-user statistics, keyed by user id since toml keys have to be strings.
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub(crate) struct Stats {
+    users: BTreeMap<String, UserStats>,
+}
+
+lazy_static! {
+    pub(crate) static ref STATS: Arc<Mutex<Stats>> =
+        Arc::new(Mutex::new(settings::init_config(STATS_PATH).unwrap()));
+}
+
+impl Stats {
+    pub(crate) fn get(&self, user: u64) -> UserStats {
+        self.users
+            .get(&user.to_string())
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Applies `f` to the statistics of each of `users` and saves them.
+pub(crate) fn update<F: FnMut(u64, &mut UserStats)>(
+    users: &[u64],
+    mut f: F,
+) -> Result<(), BotError> {
+    let stats = &mut *STATS.lock().unwrap();
+    for user in users {
+        f(*user, stats.users.entry(user.to_string()).or_default());
+    }
+    settings::sync_config(stats, STATS_PATH)
+}
@@ -0,0 +1,258 @@
+use super::bot::{self, CheckResult, Lang, Status};
+use super::commands::parser::SurvivalOption;
+use super::history;
+use super::sort::Sorted;
+use crate::try_say;
+use indexmap::{IndexMap, IndexSet};
+use itertools::Itertools;
+use rand::seq::SliceRandom;
+use serenity::client::Context;
+use serenity::http::Http;
+use serenity::model::channel::Message;
+use serenity::model::id::ChannelId;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The time limit of each round is this ratio of the previous one.
+const SHRINK_RATE: f32 = 0.9;
+
+const MIN_LIMIT_SECS: f32 = 5.0;
+
+/// Words of the first round have at least this many letters,
+/// and one more every `ESCALATION_ROUNDS` rounds.
+const BASE_LENGTH: usize = 4;
+
+const ESCALATION_ROUNDS: u32 = 2;
+
+#[derive(Debug)]
+pub struct Survival {
+    pub alive: IndexMap<u64, String>,
+    /// Players who solved the current round.
+    pub solved: IndexSet<u64>,
+    /// Players eliminated in each round.
+    pub eliminated: Vec<Vec<String>>,
+    pub round: u32,
+    /// Time limit of the current round in seconds.
+    pub limit: f32,
+    pub started: Instant,
+    pub languages: Vec<Lang>,
+}
+
+impl Survival {
+    fn new(players: IndexMap<u64, String>, option: &SurvivalOption) -> Self {
+        Survival {
+            alive: players,
+            solved: IndexSet::new(),
+            eliminated: Vec::new(),
+            round: 0,
+            limit: option.limit as f32,
+            started: Instant::now(),
+            languages: option
+                .languages
+                .iter()
+                .map(|lang| Lang::from(lang.as_str()))
+                .collect(),
+        }
+    }
+
+    /// Picks a word of the current round, which gets longer as the rounds go on.
+    fn pick(&self) -> (String, Lang) {
+        let rng = &mut rand::thread_rng();
+        let lang = *self.languages.choose(rng).unwrap();
+        let dic = bot::get_dictionary(lang);
+        let length = BASE_LENGTH + ((self.round - 1) / ESCALATION_ROUNDS) as usize;
+        let ans = dic
+            .get_except(rng, |word| {
+                UnicodeSegmentation::graphemes(word, true).count() < length
+            })
+            .unwrap_or_else(|| dic.get(rng))
+            .to_string();
+        (ans, lang)
+    }
+
+    /// Final ranking, where players eliminated in the same round share the rank.
+    fn ranking(&self) -> String {
+        use ordinal::Ordinal;
+        let rounds = self.eliminated.len();
+        let mut groups = vec![(rounds, self.alive.values().cloned().collect::<Vec<_>>())];
+        groups.extend(
+            self.eliminated
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(round, names)| (round, names.clone())),
+        );
+        let mut rank = 1;
+        let mut ranking = String::new();
+        for (survived, names) in groups.into_iter().filter(|(_, names)| !names.is_empty()) {
+            for name in names.iter() {
+                ranking += &format!(
+                    "{}: {}, survived {} rounds\n",
+                    Ordinal(rank).to_string(),
+                    name,
+                    survived
+                );
+            }
+            rank += names.len();
+        }
+        ranking
+    }
+}
+
+fn say(http: &Http, channel: ChannelId, response: String) {
+    if let Err(why) = channel.say(http, response) {
+        println!("{}", why);
+    }
+}
+
+/// Starts a survival game with the registered players.
+pub(crate) fn start(
+    http: &Arc<Http>,
+    channel: ChannelId,
+    quiz: &mut Status,
+    players: IndexMap<u64, String>,
+    option: SurvivalOption,
+) {
+    if players.len() < 2 {
+        say(
+            http,
+            channel,
+            "参加者が2人以上必要なためサバイバルを中止します。".to_string(),
+        );
+        *quiz = Status::StandingBy;
+        return;
+    }
+    say(
+        http,
+        channel,
+        format!(
+            "エントリーを締め切りました。\n参加者: {}\nサバイバルを始めます。時間内に解けなかった人は脱落です！",
+            players.values().join(", ")
+        ),
+    );
+    next_round(http, channel, quiz, Survival::new(players, &option));
+}
+
+fn next_round(http: &Arc<Http>, channel: ChannelId, quiz: &mut Status, mut survival: Survival) {
+    if survival.round > 0 {
+        survival.limit = (survival.limit * SHRINK_RATE).max(MIN_LIMIT_SECS.min(survival.limit));
+    }
+    survival.round += 1;
+    survival.solved.clear();
+    let (ans, lang) = survival.pick();
+    if let Err(why) = history::record(*channel.as_u64(), lang, &ans) {
+        println!("{}", why);
+    }
+    say(
+        http,
+        channel,
+        format!(
+            "ラウンド {round} (残り {alive} 人, 制限時間 {limit:.0} 秒)\nソートなぞなぞ ソート前の {symbol} な〜んだ？\n`{prob}`",
+            round = survival.round,
+            alive = survival.alive.len(),
+            limit = survival.limit,
+            symbol = lang.as_symbol(),
+            prob = ans.sorted(),
+        ),
+    );
+    survival.started = Instant::now();
+    let (started, limit) = (survival.started, Duration::from_secs_f32(survival.limit));
+    *quiz = Status::Surviving(ans, lang, survival);
+    let http = http.clone();
+    thread::spawn(move || {
+        thread::sleep(limit);
+        close_round(&http, channel, started);
+    });
+}
+
+/// Ends the round started at `started` on its deadline, unless it has already ended.
+fn close_round(http: &Arc<Http>, channel: ChannelId, started: Instant) {
+    let quiz = &mut *bot::QUIZ.lock().unwrap();
+    match quiz {
+        Status::Surviving(_, _, survival) if survival.started == started => {
+            end_round(http, channel, quiz)
+        }
+        _ => {}
+    }
+}
+
+/// Eliminates the players who did not solve the round, and goes on to the next one
+/// unless only one is left.
+fn end_round(http: &Arc<Http>, channel: ChannelId, quiz: &mut Status) {
+    let (ans, mut survival) = match std::mem::replace(quiz, Status::StandingBy) {
+        Status::Surviving(ans, _, survival) => (ans, survival),
+        other => {
+            *quiz = other;
+            return;
+        }
+    };
+    let solved = &survival.solved;
+    let (alive, out): (IndexMap<_, _>, IndexMap<_, _>) = survival
+        .alive
+        .drain(..)
+        .partition(|(id, _)| solved.contains(id));
+    let mut response = format!(
+        "ラウンド {} 終了。正解は \"{}\" でした...\n",
+        survival.round, ans
+    );
+    response += &if out.is_empty() {
+        "脱落者はいません。".to_string()
+    } else if alive.is_empty() {
+        "全員が脱落しました。".to_string()
+    } else {
+        format!("脱落: {}", out.values().join(", "))
+    };
+    survival.alive = alive;
+    survival
+        .eliminated
+        .push(out.into_iter().map(|(_, name)| name).collect());
+    if survival.alive.len() <= 1 {
+        say(
+            http,
+            channel,
+            format!(
+                "{}\nサバイバルが終了しました。\n{}",
+                response,
+                survival.ranking()
+            ),
+        );
+    } else {
+        say(http, channel, response);
+        next_round(http, channel, quiz, survival);
+    }
+}
+
+/// Counts a correct answer of an alive player, without revealing it.
+pub(crate) fn answer_check(ctx: &mut Context, msg: &Message, quiz: &mut Status) {
+    let author = *msg.author.id.as_u64();
+    let elapsed = quiz.elapsed().unwrap();
+    let correct = match quiz.answer_check(&msg.content) {
+        CheckResult::WA => false,
+        _ => true,
+    };
+    let survival = match quiz {
+        Status::Surviving(_, _, survival) => survival,
+        _ => return,
+    };
+    if !correct || !survival.alive.contains_key(&author) || !survival.solved.insert(author) {
+        return;
+    }
+    if let Err(why) = msg.channel_id.delete_message(&ctx, msg.id) {
+        println!("{}", why);
+    }
+    try_say!(
+        ctx,
+        msg,
+        format!(
+            "{} さん、正解です！ [{:.3} sec] (未正解 {} 人)",
+            &msg.author.name,
+            elapsed,
+            survival.alive.len() - survival.solved.len()
+        )
+    );
+    if survival.solved.len() == survival.alive.len() {
+        end_round(&ctx.http, msg.channel_id, quiz);
+    }
+}
@@ -0,0 +1,143 @@
+use super::bot::{self, ContestData};
+use super::rating;
+use indexmap::IndexMap;
+use itertools::Itertools;
+
+/// How the players of a team contest are split.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TeamMode {
+    /// Players choose their team by `~join <team>`.
+    Join,
+    /// Players belong to the team of the first of these roles they have.
+    Role(Vec<u64>),
+    /// Players are balanced by rating into this number of teams when the entry closes.
+    Auto(u32),
+}
+
+#[derive(Debug)]
+pub struct Teams {
+    pub mode: TeamMode,
+    /// Team name of each player id.
+    pub roster: IndexMap<u64, String>,
+}
+
+fn auto_team_name(index: usize) -> String {
+    ((b'A' + index as u8) as char).to_string()
+}
+
+impl Teams {
+    pub fn new(mode: TeamMode) -> Self {
+        Teams {
+            mode,
+            roster: IndexMap::new(),
+        }
+    }
+
+    /// Decides the team of a joining player. `Ok(None)` means it is decided when the entry closes.
+    pub fn join(
+        &mut self,
+        player: u64,
+        team: Option<&str>,
+        roles: &[u64],
+    ) -> Result<Option<String>, &'static str> {
+        let name = match &self.mode {
+            TeamMode::Join => match team {
+                Some(team) => team.to_string(),
+                None => return Err("`~join <チーム名>` の形式でチームを指定してください。"),
+            },
+            TeamMode::Role(team_roles) => match team_roles.iter().find(|role| roles.contains(role))
+            {
+                Some(role) => format!("<@&{}>", role),
+                None => return Err("チームのロールを持っていないためエントリーできません。"),
+            },
+            TeamMode::Auto(num) if *num == 0 || self.roster.is_empty() => return Ok(None),
+            TeamMode::Auto(num) => {
+                // late joiners go to the smallest team.
+                let num = *num as usize;
+                (0..num)
+                    .map(auto_team_name)
+                    .min_by_key(|name| self.roster.values().filter(|team| *team == name).count())
+                    .unwrap()
+            }
+        };
+        self.roster.insert(player, name.clone());
+        Ok(Some(name))
+    }
+
+    pub fn leave(&mut self, player: u64) {
+        self.roster.shift_remove(&player);
+    }
+
+    /// Snake-drafts the players into teams in descending order of rating.
+    pub fn balance(&mut self, players: &IndexMap<u64, String>) {
+        let num = match self.mode {
+            TeamMode::Auto(num) if num > 0 => num as usize,
+            _ => return,
+        };
+        let ratings = rating::RATINGS.lock().unwrap();
+        let ranked = players
+            .iter()
+            .sorted_by_key(|(id, _)| -ratings.get(**id))
+            .collect::<Vec<_>>();
+        for (index, (id, _)) in ranked.into_iter().enumerate() {
+            let round = index / num;
+            let pick = index % num;
+            let team = if round % 2 == 0 { pick } else { num - 1 - pick };
+            self.roster.insert(*id, auto_team_name(team));
+        }
+    }
+
+    /// Player names of each team, in the order the teams were formed.
+    fn members<'a>(&self, players: &'a IndexMap<u64, String>) -> IndexMap<&str, Vec<&'a String>> {
+        let mut members = IndexMap::<&str, Vec<&String>>::new();
+        for (id, team) in self.roster.iter() {
+            if let Some(name) = players.get(id) {
+                members.entry(team.as_str()).or_default().push(name);
+            }
+        }
+        members
+    }
+
+    pub fn as_string(&self, players: &IndexMap<u64, String>) -> String {
+        self.members(players)
+            .iter()
+            .map(|(team, names)| format!("{}: {}", team, names.iter().join(", ")))
+            .join("\n")
+    }
+
+    /// Ranks the teams by the solves of their members, with the best member as MVP.
+    pub fn aggregates(
+        &self,
+        players: &IndexMap<u64, String>,
+        contest_result: &IndexMap<String, ContestData>,
+    ) -> String {
+        use ordinal::Ordinal;
+        self.members(players)
+            .into_iter()
+            .map(|(team, names)| {
+                let mut total = ContestData::default();
+                for name in names.iter() {
+                    if let Some(data) = contest_result.get(*name) {
+                        total += data;
+                    }
+                }
+                let mvp = bot::standings(contest_result, &[])
+                    .into_iter()
+                    .find(|(name, data)| names.contains(&name) && !data.time.is_empty())
+                    .map_or("-".to_string(), |(name, _)| name);
+                (team, total, mvp)
+            })
+            .sorted_by_key(|(_, total, _)| total.key())
+            .enumerate()
+            .map(|(index, (team, total, mvp))| {
+                format!(
+                    "{}: {}, {} (MVP: {})\n",
+                    Ordinal(index + 1).to_string(),
+                    team,
+                    total.as_string(),
+                    mvp
+                )
+            })
+            .collect::<String>()
+    }
+}
@@ -0,0 +1,577 @@
+use super::commands::parser;
+use super::duel::{self, Duel, Player};
+use super::error::BotError;
+use super::rating;
+use super::settings;
+use itertools::Itertools;
+use serde_derive::{Deserialize, Serialize};
+use serenity::http::Http;
+use serenity::model::id::ChannelId;
+use std::sync::{Arc, Mutex};
+
+const TOURNAMENT_PATH: &str = "/tmp/settings/tournament.toml";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Entrant {
+    pub(crate) id: u64,
+    pub(crate) name: String,
+}
+
+/// A side of a match. It is `ready` once the match feeding it is decided,
+/// and has no player if that match produced none (a bye).
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub(crate) struct Slot {
+    pub(crate) player: Option<u64>,
+    pub(crate) ready: bool,
+}
+
+/// Where the winner or the loser of a match goes.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub(crate) struct Target {
+    pub(crate) id: u32,
+    pub(crate) slot: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Match {
+    pub(crate) id: u32,
+    /// `W1` for the first round of the winners bracket, `L1` of the losers bracket, `GF` for the grand final,
+    /// and `GF2` for the bracket reset played when the finalist of the losers bracket wins the grand final.
+    pub(crate) label: String,
+    pub(crate) slots: Vec<Slot>,
+    pub(crate) winner: Option<u64>,
+    #[serde(default)]
+    pub(crate) done: bool,
+    pub(crate) win_to: Option<Target>,
+    pub(crate) lose_to: Option<Target>,
+    /// The channel where the match is being played.
+    pub(crate) channel: Option<u64>,
+}
+
+impl Match {
+    fn new(id: u32, label: String) -> Self {
+        Match {
+            id,
+            label,
+            slots: vec![Slot::default(), Slot::default()],
+            winner: None,
+            done: false,
+            win_to: None,
+            lose_to: None,
+            channel: None,
+        }
+    }
+
+    fn is_playable(&self) -> bool {
+        !self.done
+            && self
+                .slots
+                .iter()
+                .all(|slot| slot.ready && slot.player.is_some())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Tournament {
+    pub(crate) organizer: u64,
+    /// The channel where the tournament was opened and its progress is announced.
+    pub(crate) channel: u64,
+    pub(crate) double: bool,
+    /// Number of problems of each match.
+    pub(crate) num: u32,
+    /// Languages in the `en:3` form accepted by `~contest`.
+    pub(crate) languages: Vec<String>,
+    pub(crate) entrants: Vec<Entrant>,
+    pub(crate) started: bool,
+    pub(crate) matches: Vec<Match>,
+    pub(crate) champion: Option<u64>,
+    /// Tells the results of duels started for a cancelled tournament from those of this one.
+    #[serde(default)]
+    pub(crate) generation: u32,
+}
+
+#[derive(Default, Debug, Serialize, Deserialize)]
+pub(crate) struct TournamentState {
+    pub(crate) tournament: Option<Tournament>,
+    /// Number of the tournaments opened so far.
+    #[serde(default)]
+    pub(crate) generation: u32,
+}
+
+lazy_static! {
+    pub(crate) static ref TOURNAMENT: Arc<Mutex<TournamentState>> = {
+        let mut state: TournamentState = settings::init_config(TOURNAMENT_PATH).unwrap();
+        // duels do not survive a restart, so their matches have to be played again.
+        if let Some(tournament) = state.tournament.as_mut() {
+            for game in tournament.matches.iter_mut() {
+                game.channel = None;
+            }
+        }
+        Arc::new(Mutex::new(state))
+    };
+}
+
+pub(crate) fn sync(state: &TournamentState) -> Result<(), BotError> {
+    settings::sync_config(state, TOURNAMENT_PATH)
+}
+
+/// Bracket positions of seeds, so that the top seeds meet as late as possible.
+fn seed_order(size: usize) -> Vec<usize> {
+    let mut order = vec![0];
+    while order.len() < size {
+        let len = order.len() * 2;
+        order = order
+            .into_iter()
+            .flat_map(|seed| vec![seed, len - 1 - seed])
+            .collect();
+    }
+    order
+}
+
+impl Tournament {
+    pub(crate) fn new(
+        organizer: u64,
+        channel: u64,
+        generation: u32,
+        option: parser::TournamentOption,
+    ) -> Self {
+        Tournament {
+            organizer,
+            channel,
+            double: option.double,
+            num: option.num,
+            languages: option
+                .languages
+                .iter()
+                .map(|(lang, weight)| format!("{}:{}", lang, weight))
+                .collect(),
+            entrants: Vec::new(),
+            started: false,
+            matches: Vec::new(),
+            champion: None,
+            generation,
+        }
+    }
+
+    pub(crate) fn name(&self, id: Option<u64>) -> String {
+        id.and_then(|id| self.entrants.iter().find(|entrant| entrant.id == id))
+            .map_or("-".to_string(), |entrant| entrant.name.clone())
+    }
+
+    fn add_match(&mut self, label: String) -> u32 {
+        let id = self.matches.len() as u32 + 1;
+        self.matches.push(Match::new(id, label));
+        id
+    }
+
+    fn get_mut(&mut self, id: u32) -> &mut Match {
+        &mut self.matches[id as usize - 1]
+    }
+
+    pub(crate) fn get(&self, id: u32) -> Option<&Match> {
+        self.matches.get((id as usize).wrapping_sub(1))
+    }
+
+    /// Builds the bracket, seeding the entrants by rating.
+    pub(crate) fn build(&mut self) {
+        let ratings = rating::RATINGS.lock().unwrap();
+        let seeds = self
+            .entrants
+            .iter()
+            .sorted_by_key(|entrant| -ratings.get(entrant.id))
+            .map(|entrant| entrant.id)
+            .collect::<Vec<_>>();
+        drop(ratings);
+        let size = seeds.len().next_power_of_two().max(2);
+        let rounds = size.trailing_zeros() as usize;
+        self.matches.clear();
+        // winners[r][i] is the id of the i-th match of the (r + 1)-th round.
+        let mut winners: Vec<Vec<u32>> = Vec::new();
+        for round in 0..rounds {
+            let ids = (0..size >> (round + 1))
+                .map(|_| self.add_match(format!("W{}", round + 1)))
+                .collect::<Vec<_>>();
+            if round > 0 {
+                for (i, id) in winners[round - 1].clone().into_iter().enumerate() {
+                    self.get_mut(id).win_to = Some(Target {
+                        id: ids[i / 2],
+                        slot: i % 2,
+                    });
+                }
+            }
+            winners.push(ids);
+        }
+        for (position, seed) in seed_order(size).into_iter().enumerate() {
+            let slot = &mut self.get_mut(winners[0][position / 2]).slots[position % 2];
+            slot.player = seeds.get(seed).cloned();
+            slot.ready = true;
+        }
+        if self.double {
+            self.build_losers(&winners);
+        }
+        self.started = true;
+        self.resolve();
+    }
+
+    /// Adds the losers bracket and the grand final after the winners bracket.
+    fn build_losers(&mut self, winners: &[Vec<u32>]) {
+        let rounds = winners.len();
+        let last_winner = *winners[rounds - 1].last().unwrap();
+        // the last match of the losers bracket, which does not exist for two players.
+        let finalist = if rounds == 1 {
+            None
+        } else {
+            // losers of the first round meet each other.
+            let mut previous = Vec::new();
+            for pair in winners[0].chunks(2) {
+                let id = self.add_match("L1".to_string());
+                for (slot, loser) in pair.iter().enumerate() {
+                    self.get_mut(*loser).lose_to = Some(Target { id, slot });
+                }
+                previous.push(id);
+            }
+            let mut label = 1;
+            for round in 1..rounds {
+                // survivors of the losers bracket meet losers of the winners bracket.
+                label += 1;
+                let mut current = Vec::new();
+                for (i, loser) in winners[round].iter().enumerate() {
+                    let id = self.add_match(format!("L{}", label));
+                    self.get_mut(previous[i]).win_to = Some(Target { id, slot: 0 });
+                    self.get_mut(*loser).lose_to = Some(Target { id, slot: 1 });
+                    current.push(id);
+                }
+                previous = current;
+                if round + 1 < rounds {
+                    label += 1;
+                    let mut current = Vec::new();
+                    for pair in previous.chunks(2) {
+                        let id = self.add_match(format!("L{}", label));
+                        for (slot, survivor) in pair.iter().enumerate() {
+                            self.get_mut(*survivor).win_to = Some(Target { id, slot });
+                        }
+                        current.push(id);
+                    }
+                    previous = current;
+                }
+            }
+            Some(previous[0])
+        };
+        let final_id = self.add_match("GF".to_string());
+        self.get_mut(last_winner).win_to = Some(Target {
+            id: final_id,
+            slot: 0,
+        });
+        let target = Some(Target {
+            id: final_id,
+            slot: 1,
+        });
+        match finalist {
+            Some(id) => self.get_mut(id).win_to = target,
+            None => self.get_mut(last_winner).lose_to = target,
+        }
+    }
+
+    /// Decides the match `id` and moves its players on.
+    pub(crate) fn decide(&mut self, id: u32, winner: Option<u64>) {
+        let double = self.double;
+        let game = self.get_mut(id);
+        let loser = game
+            .slots
+            .iter()
+            .filter_map(|slot| slot.player)
+            .find(|player| Some(*player) != winner);
+        // the finalist of the losers bracket has lost once, so their win of the grand final resets the bracket.
+        let is_reset =
+            double && game.label == "GF" && loser.is_some() && winner == game.slots[1].player;
+        game.winner = winner;
+        game.done = true;
+        game.channel = None;
+        let (win_to, lose_to) = (game.win_to, game.lose_to);
+        match win_to {
+            Some(target) => {
+                self.get_mut(target.id).slots[target.slot] = Slot {
+                    player: winner,
+                    ready: true,
+                }
+            }
+            None if is_reset => {
+                let reset = self.add_match("GF2".to_string());
+                self.get_mut(reset).slots = vec![
+                    Slot {
+                        player: loser,
+                        ready: true,
+                    },
+                    Slot {
+                        player: winner,
+                        ready: true,
+                    },
+                ];
+            }
+            None => self.champion = winner,
+        }
+        if let Some(target) = lose_to {
+            self.get_mut(target.id).slots[target.slot] = Slot {
+                player: loser,
+                ready: true,
+            };
+        }
+    }
+
+    /// Advances every match which has a bye.
+    fn resolve(&mut self) {
+        while let Some((id, winner)) = self
+            .matches
+            .iter()
+            .find(|game| {
+                !game.done
+                    && game.slots.iter().all(|slot| slot.ready)
+                    && game.slots.iter().any(|slot| slot.player.is_none())
+            })
+            .map(|game| (game.id, game.slots.iter().find_map(|slot| slot.player)))
+        {
+            self.decide(id, winner);
+        }
+    }
+
+    pub(crate) fn playable(&self) -> Vec<&Match> {
+        self.matches
+            .iter()
+            .filter(|game| game.is_playable() && game.channel.is_none())
+            .collect()
+    }
+
+    /// The duel deciding the match `id` in `channel`.
+    fn duel(&self, id: u32, channel: u64) -> Duel {
+        let mut players = self.get(id).unwrap().slots.iter().map(|slot| {
+            let player = slot.player.unwrap();
+            Player::new(player, self.name(Some(player)))
+        });
+        let mut game = Duel::new(
+            channel,
+            [players.next().unwrap(), players.next().unwrap()],
+            self.num,
+            self.num / 2 + 1,
+            self.languages
+                .iter()
+                .map(|language| parser::parse_language(language))
+                .collect(),
+        );
+        game.tournament = Some((self.generation, id));
+        game
+    }
+
+    /// Assigns the playable matches to the channel of the tournament, except those with a player `busy` in another duel.
+    /// Their duels are returned to be begun by the holder of `DUELS`.
+    fn start_matches<F: Fn(u64) -> bool>(&mut self, busy: F) -> Vec<Duel> {
+        let ids = self
+            .playable()
+            .iter()
+            .filter(|game| !game.slots.iter().any(|slot| busy(slot.player.unwrap())))
+            .map(|game| game.id)
+            .collect::<Vec<_>>();
+        let channel = self.channel;
+        ids.into_iter()
+            .map(|id| {
+                self.get_mut(id).channel = Some(channel);
+                self.duel(id, channel)
+            })
+            .collect()
+    }
+
+    /// Lists the matches of `started` and those left to `~tournament play`.
+    fn progress(&self, started: &[Duel]) -> String {
+        let mut lines = Vec::new();
+        if !started.is_empty() {
+            lines.push(format!(
+                "開始した試合:\n{}",
+                started
+                    .iter()
+                    .filter_map(|game| self.get(game.tournament?.1))
+                    .map(|game| self.match_string(game))
+                    .join("\n")
+            ));
+        }
+        let playable = self.playable();
+        if !playable.is_empty() {
+            lines.push(format!(
+                "対戦可能な試合 (`~tournament play <番号>` で開始):\n{}",
+                playable
+                    .iter()
+                    .map(|game| self.match_string(game))
+                    .join("\n")
+            ));
+        }
+        lines.join("\n")
+    }
+
+    pub(crate) fn match_string(&self, game: &Match) -> String {
+        let players = game
+            .slots
+            .iter()
+            .map(|slot| match (slot.ready, slot.player) {
+                (false, _) => "?".to_string(),
+                (true, player) => self.name(player),
+            })
+            .join(" vs ");
+        let state = if game.done {
+            format!(" → {}", self.name(game.winner))
+        } else if game.channel.is_some() {
+            " (対戦中)".to_string()
+        } else {
+            String::new()
+        };
+        format!("#{} {}: {}{}", game.id, game.label, players, state)
+    }
+
+    pub(crate) fn as_string(&self) -> String {
+        if !self.started {
+            return format!(
+                "{}トーナメント (エントリー受付中, 1試合 {}問)\n参加者: {}",
+                if self.double {
+                    "ダブルエリミネーション"
+                } else {
+                    "シングルエリミネーション"
+                },
+                self.num,
+                self.entrants.iter().map(|entrant| &entrant.name).join(", ")
+            );
+        }
+        let mut lines = self
+            .matches
+            .iter()
+            .map(|game| self.match_string(game))
+            .collect::<Vec<_>>();
+        if self.champion.is_some() {
+            lines.push(format!("優勝: {}", self.name(self.champion)));
+        }
+        lines.join("\n")
+    }
+}
+
+fn say(http: &Http, channel: u64, response: String) {
+    if let Err(why) = ChannelId::from(channel).say(http, response) {
+        println!("{}", why);
+    }
+}
+
+/// Starts the match `id` as a duel in `channel`, if `user` is one of its players.
+/// Matches are started in the channel of the tournament when they become playable,
+/// so this is for those left because a player was in another duel, or to play in another channel.
+pub(crate) fn play(http: &Http, channel: ChannelId, user: u64, id: u32) -> Result<(), String> {
+    let (players, generation) = {
+        let state = TOURNAMENT.lock().unwrap();
+        let tournament = match state.tournament.as_ref() {
+            Some(tournament) if tournament.started => tournament,
+            _ => return Err("開催中のトーナメントはありません。".to_string()),
+        };
+        let game = match tournament.get(id) {
+            Some(game) if game.is_playable() && game.channel.is_none() => game,
+            _ => return Err(format!("試合 #{} は開始できません。", id)),
+        };
+        if !game.slots.iter().any(|slot| slot.player == Some(user)) {
+            return Err(format!("試合 #{} の選手ではありません。", id));
+        }
+        let players = game
+            .slots
+            .iter()
+            .filter_map(|slot| slot.player)
+            .collect::<Vec<_>>();
+        (players, tournament.generation)
+    };
+    // `DUELS` is locked before `TOURNAMENT` when a duel reports its result.
+    if players
+        .iter()
+        .any(|player| duel::is_dueling(&duel::DUELS.lock().unwrap(), *player))
+    {
+        return Err("どちらかの選手が決闘中です。".to_string());
+    }
+    let game = {
+        let state = &mut *TOURNAMENT.lock().unwrap();
+        let tournament = match state.tournament.as_mut() {
+            Some(tournament) if tournament.generation == generation => tournament,
+            _ => return Err("開催中のトーナメントはありません。".to_string()),
+        };
+        if tournament
+            .get(id)
+            .map_or(true, |game| game.channel.is_some())
+        {
+            return Err(format!("試合 #{} は開始できません。", id));
+        }
+        tournament.get_mut(id).channel = Some(*channel.as_u64());
+        let game = tournament.duel(id, *channel.as_u64());
+        if let Err(why) = sync(state) {
+            println!("{}", why);
+        }
+        game
+    };
+    duel::start(http, game);
+    Ok(())
+}
+
+/// Starts the playable matches of the current tournament in its channel.
+/// The duels are returned to the caller, which holds `DUELS` as `duels`.
+pub(crate) fn start_matches(http: &Http, duels: &[Duel]) -> Vec<Duel> {
+    let state = &mut *TOURNAMENT.lock().unwrap();
+    let tournament = match state.tournament.as_mut() {
+        Some(tournament) if tournament.started && tournament.champion.is_none() => tournament,
+        _ => return Vec::new(),
+    };
+    let started = tournament.start_matches(|player| duel::is_dueling(duels, player));
+    let progress = tournament.progress(&started);
+    if !progress.is_empty() {
+        say(http, tournament.channel, progress);
+    }
+    if let Err(why) = sync(state) {
+        println!("{}", why);
+    }
+    started
+}
+
+/// Records the result of the match `id` and starts the matches which became playable,
+/// whose duels are returned to the caller holding `DUELS` as `duels`.
+/// Results of a tournament other than the current `generation` are ignored.
+pub(crate) fn report(
+    http: &Http,
+    generation: u32,
+    id: u32,
+    winner: u64,
+    duels: &[Duel],
+) -> Vec<Duel> {
+    let state = &mut *TOURNAMENT.lock().unwrap();
+    let tournament = match state.tournament.as_mut() {
+        Some(tournament) if tournament.generation == generation => tournament,
+        _ => return Vec::new(),
+    };
+    if tournament.get(id).map_or(true, |game| game.done) {
+        return Vec::new();
+    }
+    tournament.decide(id, Some(winner));
+    tournament.resolve();
+    let mut response = format!(
+        "トーナメント {}",
+        tournament.match_string(tournament.get(id).unwrap())
+    );
+    let started = match tournament.champion {
+        Some(champion) => {
+            response += &format!(
+                "\nトーナメントが終了しました。優勝は {} さんです！",
+                tournament.name(Some(champion))
+            );
+            Vec::new()
+        }
+        None => {
+            let started = tournament.start_matches(|player| duel::is_dueling(duels, player));
+            let progress = tournament.progress(&started);
+            if !progress.is_empty() {
+                response += &format!("\n{}", progress);
+            }
+            started
+        }
+    };
+    say(http, tournament.channel, response);
+    if let Err(why) = sync(state) {
+        println!("{}", why);
+    }
+    started
+}